@@ -0,0 +1,163 @@
+use clap::Args;
+use lenticular_core::{image::Rgb8Color, units::Length};
+
+use crate::{
+    parse_rgb8, AnchorArg, BlackGenerationMode, FitArg, PreviewConversion, ScaleAlgorithm,
+    ScreenMode,
+};
+
+/// 输入文件
+#[derive(Debug, Args)]
+pub struct SourceArgs {
+    /// 输入文件，可以为多个。若输入多个文件，请保证文件数量与后续多个参数数量一致。
+    ///
+    /// 支持 TIFF（CMYK/RGB）以及 PNG/JPEG/WebP/BMP 等常见格式（按 RGB 处理）。
+    ///
+    /// 多页 TIFF 的每一页、GIF/APNG/WebP 动画的每一帧按顺序作为单独的帧，共用该文件的采用数量。
+    #[clap(short, long, required = true)]
+    pub input: Vec<String>,
+    /// 动画输入按等间隔抽取到的帧数。不输入时使用全部帧。
+    #[clap(long)]
+    pub animation_frames: Option<usize>,
+}
+
+/// 光栅与成品的物理参数
+#[derive(Debug, Args)]
+pub struct LensArgs {
+    /// 光栅线宽，单位：光栅数/英寸(LPI)
+    #[clap(long)]
+    pub lpi: f64,
+    /// 输出图像宽度，支持 mm/cm/in/pt 单位，如 85.6mm；不带单位时按毫米(mm)处理。
+    ///
+    /// 不输入时按第一张图的宽高比由高度计算。
+    #[clap(long, required_unless_present = "output_height")]
+    pub output_width: Option<Length>,
+    /// 输出图像高度，单位同 --output-width。不输入时按第一张图的宽高比计算。
+    ///
+    /// 同时指定宽度与高度时（如卡片尺寸），建议配合 --fit 使用。
+    #[clap(long)]
+    pub output_height: Option<Length>,
+}
+
+/// 适配方式与印后加工版面
+#[derive(Debug, Args)]
+pub struct LayoutArgs {
+    /// 输入图像与输出画幅宽高比不一致时的适配方式
+    #[clap(long, value_enum, default_value_t = FitArg::Stretch)]
+    pub fit: FitArg,
+    /// 填满模式下的裁切锚点
+    #[clap(long, value_enum, default_value_t = AnchorArg::Center)]
+    pub anchor: AnchorArg,
+    /// 完整显示模式下的背景色，格式：RRGGBB
+    #[clap(long, default_value = "ffffff", value_parser = parse_rgb8)]
+    pub background: Rgb8Color,
+    /// 出血宽度，单位同 --output-width。水平方向向上取整到整数个光栅。
+    #[clap(long)]
+    pub bleed: Option<Length>,
+    /// 安全边距，单位同 --output-width。在 CMYK 8位输出中绘制参考线，仅用于打样检查。
+    #[clap(long)]
+    pub safe_margin: Option<Length>,
+    /// 在 CMYK 8位输出的出血以外绘制裁切标记
+    #[clap(long, default_value_t = false)]
+    pub crop_marks: bool,
+    /// 在 CMYK 8位输出的成品上下两侧绘制光栅对位条（光栅线与帧色条）
+    #[clap(long, default_value_t = false)]
+    pub registration_bars: bool,
+    /// 背面印刷（直接印在光栅板平面一侧）：镜像各帧并反转光栅内的帧顺序
+    #[clap(long, default_value_t = false)]
+    pub reverse_print: bool,
+}
+
+/// 分色与色彩管理
+#[derive(Debug, Args)]
+pub struct ColorArgs {
+    /// RGB 输入时分色为 CMYK 8位输出。CMYK 输入不受影响。
+    #[clap(long, default_value_t = false)]
+    pub cmyk: bool,
+    /// 分色时的黑版生成方式
+    #[clap(long, value_enum, default_value_t = BlackGenerationMode::Gcr)]
+    pub black_generation: BlackGenerationMode,
+    /// 黑版替代灰成分的比例，范围 0~1
+    #[clap(long, default_value_t = 0.7)]
+    pub black_amount: f64,
+    /// 分色时的总墨量上限，单位：百分比(%)
+    #[clap(long, default_value_t = 300.0)]
+    pub total_ink: f64,
+    /// 嵌入输出图像的 ICC 配置文件(.icc)。不输入时沿用输入图像中的配置文件。
+    #[clap(long)]
+    pub icc_profile: Option<String>,
+}
+
+/// 一次光栅合成的参数，对应任务文件
+#[derive(Debug, Args)]
+pub struct JobArgs {
+    #[command(flatten)]
+    pub source: SourceArgs,
+    /// 指定每个文件的采用数量。
+    ///
+    /// 若输入多个文件，则每个文件对应一个值。
+    ///
+    /// 若该参数只设置一个，则所有文件都使用该值。不输入时，默认为1。
+    #[clap(short, long)]
+    pub count: Option<Vec<u32>>,
+    #[command(flatten)]
+    pub lens: LensArgs,
+    #[command(flatten)]
+    pub layout: LayoutArgs,
+    #[command(flatten)]
+    pub color: ColorArgs,
+    /// 缩放算法
+    #[clap(long, value_enum, default_value_t = ScaleAlgorithm::Nearest)]
+    pub scale_algorithm: ScaleAlgorithm,
+    /// 使用 LZW 压缩输出 TIFF 图像，拼版、分块与白墨通道等附加 TIFF 输出同样压缩
+    #[clap(long, default_value_t = false)]
+    pub lzw: bool,
+    /// 16位输入时，将输出转换为 8位
+    #[clap(long, default_value_t = false)]
+    pub to_8bit: bool,
+    /// 转换为 8位时使用误差扩散抖动，减少渐变色带
+    #[clap(long, default_value_t = false, requires = "to_8bit")]
+    pub dither: bool,
+}
+
+/// 加网参数，调幅网参数同时用于龟纹风险分析
+#[derive(Debug, Args)]
+pub struct ScreenArgs {
+    /// 加网输出 1 位分色版(TIFF)，按 <输出文件名>_C/M/Y/K.tif 写入输出文件所在目录，仅对 CMYK 8位输出有效
+    #[clap(long, value_enum)]
+    pub screen: Option<ScreenMode>,
+    /// 加网的设备分辨率，单位：DPI。不输入时使用输出图像的分辨率。
+    #[clap(long)]
+    pub screen_dpi: Option<f64>,
+    /// 调幅网的网线频率，单位：LPI
+    #[clap(long, default_value_t = 150.0)]
+    pub screen_frequency: f64,
+    /// 调幅网 C,M,Y,K 的网角，单位：度
+    #[clap(long, value_delimiter = ',', default_value = "15,75,0,45")]
+    pub screen_angles: Vec<f64>,
+    /// 按光栅线数修正调幅网的网线频率，减少与光栅之间的龟纹
+    #[clap(long, default_value_t = false)]
+    pub screen_lock_lens: bool,
+    /// 印刷设备的物理分辨率，单位：DPI。用于龟纹风险分析。
+    #[clap(long)]
+    pub printer_dpi: Option<f64>,
+}
+
+impl ScreenArgs {
+    /// C,M,Y,K 的网角，数量不为 4 时报错
+    pub fn angles(&self) -> anyhow::Result<[f64; 4]> {
+        <[f64; 4]>::try_from(self.screen_angles.as_slice())
+            .map_err(|_| anyhow::anyhow!("网角需依次指定 C,M,Y,K 四个值"))
+    }
+}
+
+/// 预览图参数
+#[derive(Debug, Args)]
+pub struct ProofArgs {
+    /// 预览图最大宽度，单位：像素
+    #[clap(long)]
+    pub preview_width: Option<u32>,
+    /// 预览图的颜色转换方式
+    #[clap(long, value_enum, default_value_t = PreviewConversion::Ink)]
+    pub preview_conversion: PreviewConversion,
+}
@@ -0,0 +1,478 @@
+use std::{
+    collections::{HashMap, HashSet},
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::Instant,
+};
+
+use anyhow::Context;
+use lenticular_core::{
+    decoder::{FrameDecoder, MemoryFrameDecoder},
+    lenticular::{FittedFrameCache, InputImageContext},
+};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::{interlace, job::Job, open_decoders};
+
+/// 批处理清单，按顺序列出任务文件
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    jobs: Vec<String>,
+}
+
+type CacheKey = (PathBuf, Option<usize>);
+
+/// 一个共用输入文件的缓存项
+struct CacheEntry {
+    /// 尚未读取该文件的次数，降为 0 时移出缓存
+    remaining: usize,
+    frames: Arc<Mutex<Option<Vec<MemoryFrameDecoder>>>>,
+}
+
+/// 已解码帧的缓存，多个任务共用同一输入文件（如共同的背景）时只解码一次
+///
+/// 只缓存被两次及以上使用的文件，最后一次使用后即释放。
+#[derive(Default)]
+pub struct FrameCache {
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    hits: AtomicUsize,
+    /// 静态文件各帧裁切、缩放后的结果
+    fitted: Option<Arc<FittedFrameCache>>,
+}
+
+impl FrameCache {
+    /// 按各任务使用输入文件的次数建立缓存
+    pub fn for_jobs(jobs: &[(String, anyhow::Result<Job>)]) -> Self {
+        Self {
+            entries: Mutex::new(shared_inputs(jobs, |_| true)),
+            ..Default::default()
+        }
+    }
+
+    /// 只缓存各任务共用的静态文件，并复用其裁切、缩放结果
+    pub fn for_static_inputs(
+        static_paths: HashSet<String>,
+        jobs: &[(String, anyhow::Result<Job>)],
+    ) -> Self {
+        Self {
+            entries: Mutex::new(shared_inputs(jobs, |path| static_paths.contains(path))),
+            fitted: Some(Arc::new(FittedFrameCache::new())),
+            ..Default::default()
+        }
+    }
+
+    pub fn fitted(&self) -> Option<Arc<FittedFrameCache>> {
+        self.fitted.clone()
+    }
+
+    /// 同 [`Job::open_inputs`]，共用文件的各帧从缓存中读取
+    pub fn open_inputs(
+        &self,
+        job: &Job,
+    ) -> anyhow::Result<Vec<InputImageContext<Box<dyn FrameDecoder>>>> {
+        let mut cache_keys = vec![];
+        let inputs = job.open_inputs_with(|input| {
+            let Some(frames) = self.frames(&input.path, input.animation_frames)? else {
+                let decoders = open_decoders(&input.path, input.animation_frames)?;
+                cache_keys.extend(decoders.iter().map(|_| None));
+                return Ok(decoders);
+            };
+            cache_keys.extend((0..frames.len()).map(|index| {
+                self.fitted
+                    .as_ref()
+                    .map(|_| format!("{}#{:?}#{}", input.path, input.animation_frames, index))
+            }));
+            Ok(frames
+                .into_iter()
+                .map(|frame| Box::new(frame) as Box<dyn FrameDecoder>)
+                .collect())
+        })?;
+        Ok(inputs
+            .into_iter()
+            .zip(cache_keys)
+            .map(|(input, key)| match key {
+                Some(key) => input.with_cache_key(key),
+                None => input,
+            })
+            .collect())
+    }
+
+    /// 读取共用文件的各帧，不在缓存中的文件返回 `None`
+    fn frames(
+        &self,
+        path: &str,
+        animation_frames: Option<usize>,
+    ) -> anyhow::Result<Option<Vec<MemoryFrameDecoder>>> {
+        let key = cache_key(path, animation_frames);
+        let frames = {
+            let mut entries = lock(&self.entries);
+            let Some(entry) = entries.get_mut(&key) else {
+                return Ok(None);
+            };
+            entry.remaining -= 1;
+            let frames = entry.frames.clone();
+            if entry.remaining == 0 {
+                // 最后一次使用，解码结果随本任务结束释放
+                entries.remove(&key);
+            }
+            frames
+        };
+        // 同一文件只由一个任务解码，其余任务等待解码完成；解码中 panic 时由下一个任务重新解码
+        let mut frames = lock(&frames);
+        if let Some(frames) = frames.as_ref() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(frames.clone()));
+        }
+        let decoded = open_decoders(path, animation_frames)?
+            .iter_mut()
+            .map(MemoryFrameDecoder::from_decoder)
+            .collect::<lenticular_core::error::Result<Vec<_>>>()
+            .context(format!("读取文件 {} 失败", path))?;
+        *frames = Some(decoded.clone());
+        Ok(Some(decoded))
+    }
+}
+
+fn cache_key(path: &str, animation_frames: Option<usize>) -> CacheKey {
+    (
+        std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)),
+        animation_frames,
+    )
+}
+
+/// 统计可执行的任务中各输入文件的使用次数，保留 `filter` 接受且使用两次及以上的文件
+fn shared_inputs<F>(
+    jobs: &[(String, anyhow::Result<Job>)],
+    filter: F,
+) -> HashMap<CacheKey, CacheEntry>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut uses = HashMap::new();
+    jobs.iter()
+        .filter_map(|(_, job)| job.as_ref().ok())
+        .filter(|job| job.validate().is_ok())
+        .flat_map(|job| &job.inputs)
+        .filter(|input| filter(&input.path))
+        .for_each(|input| {
+            *uses
+                .entry(cache_key(&input.path, input.animation_frames))
+                .or_insert(0) += 1
+        });
+    uses.into_iter()
+        .filter(|(_, remaining)| *remaining >= 2)
+        .map(|(key, remaining)| {
+            let entry = CacheEntry {
+                remaining,
+                frames: Arc::default(),
+            };
+            (key, entry)
+        })
+        .collect()
+}
+
+/// 任务中的 panic 不影响其余任务继续使用缓存与结果
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 单个任务的执行结果
+#[derive(Debug, Serialize)]
+struct JobReport {
+    job: String,
+    output: Option<String>,
+    success: bool,
+    error: Option<String>,
+    elapsed_ms: u128,
+}
+
+/// 批处理汇总
+#[derive(Debug, Serialize)]
+struct BatchSummary {
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+    cache_hits: usize,
+    elapsed_ms: u128,
+    jobs: Vec<JobReport>,
+}
+
+/// 执行目录或清单中的全部任务，共用已解码帧的缓存
+///
+/// 单个任务失败时记录错误并继续执行其余任务，全部完成后若有失败则返回错误。
+pub fn run_batch(path: &str, workers: usize, summary: Option<&str>) -> anyhow::Result<()> {
+    let job_paths = collect_jobs(Path::new(path))?;
+    if job_paths.is_empty() {
+        return Err(anyhow::anyhow!("{} 中没有任务文件", path));
+    }
+    let jobs = job_paths
+        .iter()
+        .map(|path| {
+            let name = path.display().to_string();
+            let job = Job::load(&name);
+            (name, job)
+        })
+        .collect::<Vec<_>>();
+    let cache = FrameCache::for_jobs(&jobs);
+    run_jobs(jobs, &cache, workers, summary)
+}
+
+/// 按顺序分配给 `workers` 个线程执行任务，读取失败的任务直接记为失败
+pub fn run_jobs(
+    jobs: Vec<(String, anyhow::Result<Job>)>,
+    cache: &FrameCache,
+    workers: usize,
+    summary: Option<&str>,
+) -> anyhow::Result<()> {
+    info!("批处理：共 {} 个任务", jobs.len());
+
+    let start = Instant::now();
+    let next = AtomicUsize::new(0);
+    let reports = Mutex::new(Vec::with_capacity(jobs.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some((name, job)) = jobs.get(index) else {
+                    break;
+                };
+                let report = run_job(name, job, cache);
+                lock(&reports).push((index, report));
+            });
+        }
+    });
+    let mut reports = reports.into_inner().unwrap_or_else(PoisonError::into_inner);
+    reports.sort_by_key(|(index, _)| *index);
+    let reports = reports
+        .into_iter()
+        .map(|(_, report)| report)
+        .collect::<Vec<_>>();
+
+    let failed = reports.iter().filter(|report| !report.success).count();
+    let summary_data = BatchSummary {
+        total: reports.len(),
+        succeeded: reports.len() - failed,
+        failed,
+        cache_hits: cache.hits.load(Ordering::Relaxed)
+            + cache.fitted.as_ref().map_or(0, |fitted| fitted.hits()),
+        elapsed_ms: start.elapsed().as_millis(),
+        jobs: reports,
+    };
+
+    info!("批处理汇总：");
+    for report in &summary_data.jobs {
+        match &report.error {
+            None => info!("成功 {} 毫秒 {}", report.elapsed_ms, report.job),
+            Some(err) => error!("失败 {} 毫秒 {}：{}", report.elapsed_ms, report.job, err),
+        }
+    }
+    info!(
+        "成功 {} 个，失败 {} 个，缓存命中 {} 次，总耗时 {} 毫秒",
+        summary_data.succeeded,
+        summary_data.failed,
+        summary_data.cache_hits,
+        summary_data.elapsed_ms
+    );
+    if let Some(path) = summary {
+        std::fs::write(path, serde_json::to_string_pretty(&summary_data)?)
+            .context(format!("写入文件 {} 失败", path))?;
+        info!("批处理汇总已写入 {}", path);
+    }
+
+    if failed > 0 {
+        return Err(anyhow::anyhow!("{} 个任务失败", failed));
+    }
+    Ok(())
+}
+
+fn run_job(name: &str, job: &anyhow::Result<Job>, cache: &FrameCache) -> JobReport {
+    let start = Instant::now();
+    info!("开始任务 {}", name);
+    let output = job.as_ref().ok().map(|job| job.output.clone());
+    // 单个任务中的 panic 也按失败处理，不中断批处理
+    let result = panic::catch_unwind(AssertUnwindSafe(|| match job {
+        Ok(job) => interlace(job, None, Some(cache)),
+        Err(err) => Err(anyhow::anyhow!("{:#}", err)),
+    }))
+    .unwrap_or_else(|_| Err(anyhow::anyhow!("处理过程中发生 panic")));
+    if let Err(err) = &result {
+        error!("任务 {} 失败：{:#}", name, err);
+    }
+    JobReport {
+        job: name.to_string(),
+        output,
+        success: result.is_ok(),
+        error: result.err().map(|err| format!("{:#}", err)),
+        elapsed_ms: start.elapsed().as_millis(),
+    }
+}
+
+/// 列出目录中的任务文件（按文件名排序），或读取清单中的任务文件
+fn collect_jobs(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let is_job = |path: &Path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("toml") || ext.eq_ignore_ascii_case("json"))
+    };
+
+    if path.is_dir() {
+        let mut jobs = std::fs::read_dir(path)
+            .context(format!("读取目录 {} 失败", path.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        jobs.retain(|path| path.is_file() && is_job(path));
+        jobs.sort();
+        return Ok(jobs);
+    }
+
+    let text =
+        std::fs::read_to_string(path).context(format!("读取清单 {} 失败", path.display()))?;
+    let manifest: Manifest = if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+    {
+        serde_json::from_str(&text).context(format!("解析清单 {} 失败", path.display()))?
+    } else {
+        toml::from_str(&text).context(format!("解析清单 {} 失败", path.display()))?
+    };
+    let base = path.parent().unwrap_or(Path::new(""));
+    Ok(manifest.jobs.iter().map(|job| base.join(job)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufWriter};
+
+    use lenticular_core::{
+        image::{DpiInfo, MatrixImage, Rgb8Color},
+        lenticular::write_tiff_rgb8,
+    };
+
+    use super::*;
+    use crate::job::JobInput;
+
+    /// 在 `dir` 中写出 8x4 的 RGB 8位图像，返回其路径
+    fn write_frame(dir: &Path, name: &str) -> String {
+        let mut img = MatrixImage::<Rgb8Color>::new(8, 4);
+        img.set_info(DpiInfo {
+            dpi_w: 254.0,
+            dpi_h: 254.0,
+        });
+        let path = dir.join(name);
+        write_tiff_rgb8(BufWriter::new(File::create(&path).unwrap()), &img).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn job(inputs: &[&str], output: PathBuf) -> Job {
+        let text = format!(
+            "lpi = 127.0\nwidth = \"2mm\"\noutput = {:?}\ninputs = []",
+            output
+        );
+        let mut job: Job = toml::from_str(&text).unwrap();
+        job.inputs = inputs
+            .iter()
+            .map(|path| JobInput {
+                path: path.to_string(),
+                strip_width: 1,
+                animation_frames: None,
+            })
+            .collect();
+        job
+    }
+
+    #[test]
+    fn test_frame_cache() {
+        let dir = std::env::temp_dir().join(format!("lenticular-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let [shared, a, b] = ["shared.tif", "a.tif", "b.tif"].map(|name| write_frame(&dir, name));
+        let jobs = vec![
+            (
+                "a".to_string(),
+                Ok(job(&[&shared, &a], dir.join("a_out.tif"))),
+            ),
+            (
+                "b".to_string(),
+                Ok(job(&[&shared, &b], dir.join("b_out.tif"))),
+            ),
+            ("bad".to_string(), Err(anyhow::anyhow!("无法读取"))),
+        ];
+
+        // 只缓存两个任务共用的文件，最后一次使用后移出缓存
+        let cache = FrameCache::for_jobs(&jobs);
+        let remaining = |cache: &FrameCache| {
+            lock(&cache.entries)
+                .values()
+                .map(|entry| entry.remaining)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(remaining(&cache), [2]);
+        let Ok(job_a) = &jobs[0].1 else {
+            unreachable!()
+        };
+        assert_eq!(cache.open_inputs(job_a).unwrap().len(), 2);
+        assert_eq!(remaining(&cache), [1]);
+        assert_eq!(cache.hits.load(Ordering::Relaxed), 0);
+        let Ok(job_b) = &jobs[1].1 else {
+            unreachable!()
+        };
+        cache.open_inputs(job_b).unwrap();
+        assert!(remaining(&cache).is_empty());
+        assert_eq!(cache.hits.load(Ordering::Relaxed), 1);
+
+        // 缓存锁中毒后仍可继续使用
+        let cache = FrameCache::for_jobs(&jobs);
+        let _ = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _entries = cache.entries.lock().unwrap();
+                    panic!("模拟任务 panic");
+                })
+                .join()
+        });
+        assert!(cache.entries.is_poisoned());
+        assert_eq!(cache.open_inputs(job_a).unwrap().len(), 2);
+
+        // 单个任务失败时继续执行其余任务，汇总中记录失败
+        let summary = dir.join("summary.json");
+        let err = run_jobs(jobs, &cache, 2, summary.to_str()).unwrap_err();
+        assert_eq!(err.to_string(), "1 个任务失败");
+        assert!(dir.join("a_out.tif").is_file());
+        assert!(dir.join("b_out.tif").is_file());
+        let summary: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&summary).unwrap()).unwrap();
+        assert_eq!(summary["succeeded"], 2);
+        assert_eq!(summary["failed"], 1);
+        assert_eq!(summary["jobs"][2]["success"], false);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_collect_jobs() {
+        let dir = std::env::temp_dir().join(format!("lenticular-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["b.toml", "a.json", "notes.txt"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        assert_eq!(
+            collect_jobs(&dir).unwrap(),
+            vec![dir.join("a.json"), dir.join("b.toml")]
+        );
+
+        // 清单中的相对路径相对于清单所在目录
+        let manifest = dir.join("manifest.list");
+        std::fs::write(&manifest, "jobs = [\"b.toml\", \"/abs/c.toml\"]").unwrap();
+        assert_eq!(
+            collect_jobs(&manifest).unwrap(),
+            vec![dir.join("b.toml"), PathBuf::from("/abs/c.toml")]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
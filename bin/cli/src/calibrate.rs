@@ -0,0 +1,72 @@
+use std::{fs::File, io::BufWriter};
+
+use anyhow::Context;
+use clap::Args;
+use lenticular_core::{
+    lenticular::{self, pitch_test_chart, PitchTestOptions},
+    units::Length,
+};
+use log::info;
+
+/// calibrate 子命令
+#[derive(Debug, Args)]
+pub struct CalibrateArgs {
+    /// 光栅板的标称线数，单位：光栅数/英寸(LPI)
+    #[clap(long)]
+    lpi: f64,
+    /// 打印机分辨率，单位：DPI
+    #[clap(long)]
+    dpi: f64,
+    /// 相邻色带的 LPI 差值
+    #[clap(long, default_value_t = 0.1)]
+    step: f64,
+    /// 标称值两侧各生成的色带数
+    #[clap(long, default_value_t = 5)]
+    steps: u32,
+    /// 测试图宽度，支持 mm/cm/in/pt 单位；不带单位时按毫米(mm)处理。
+    #[clap(long, default_value = "50mm")]
+    width: Length,
+    /// 每条色带的高度，单位同 --width
+    #[clap(long, default_value = "5mm")]
+    band_height: Length,
+    /// 输出文件(TIFF)
+    #[clap(short, long)]
+    output: String,
+}
+
+/// 生成透镜节距测试图，并列出各色带对应的 LPI
+pub fn run_calibrate(args: &CalibrateArgs) -> anyhow::Result<()> {
+    let options = PitchTestOptions::new(args.lpi, args.dpi, args.width)
+        .with_step(args.step)
+        .with_steps(args.steps)
+        .with_band_height(args.band_height);
+    let chart = pitch_test_chart(&options)?;
+
+    let file = File::create(&args.output).context(format!("创建文件 {} 失败", args.output))?;
+    lenticular::write_tiff_cmyk8(BufWriter::new(file), &chart.image)?;
+    info!(
+        "节距测试图已写入 {}（{}x{}，{} DPI）",
+        args.output,
+        chart.image.width(),
+        chart.image.height(),
+        args.dpi
+    );
+
+    info!("色带（自上而下）：");
+    for (index, band) in chart.bands.iter().enumerate() {
+        let nominal = if index as u32 == args.steps {
+            "（标称，左侧有标记块）"
+        } else {
+            ""
+        };
+        info!(
+            "{:>2}：{:.3} LPI，距顶边 {:.1} mm{}",
+            index + 1,
+            band.lpi,
+            Length::from_px(band.y as f64, args.dpi).to_mm(),
+            nominal
+        );
+    }
+    info!("打印后透过光栅板观察，整条显示为均匀色块的色带即为实际 LPI，用作 --lpi 的值");
+    Ok(())
+}
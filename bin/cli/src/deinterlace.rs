@@ -0,0 +1,139 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use anyhow::Context;
+use clap::Args;
+use lenticular_core::{
+    decoder::FrameDecoder,
+    image::{Cmyk16Color, Cmyk8Color, MatrixImage, Rgb16Color, Rgb8Color},
+    lenticular::{self, deinterlace, read_image, InterlaceColor},
+};
+use log::{info, warn};
+
+use crate::{open_decoders, ScaleAlgorithm};
+
+type WriteFn<C> = fn(BufWriter<File>, &MatrixImage<C>) -> lenticular_core::error::Result<()>;
+
+/// deinterlace 子命令
+#[derive(Debug, Args)]
+pub struct DeinterlaceArgs {
+    /// 光栅图像(TIFF)，左边缘须与光栅边缘对齐（不含裁切标记等标记区）
+    #[clap(short, long)]
+    input: String,
+    /// 光栅内的帧数。不输入时为 --count 的参数数量。
+    #[clap(long, required_unless_present = "count")]
+    frames: Option<usize>,
+    /// 每帧在一个光栅内占用的像素列数，按帧在光栅内从左到右的位置排列。
+    ///
+    /// 若该参数只设置一个，则所有帧都使用该值。不输入时，默认为1。
+    #[clap(short, long)]
+    count: Option<Vec<u32>>,
+    /// 光栅线宽，单位：光栅数/英寸(LPI)。指定时检查图像分辨率与条纹宽度是否一致。
+    #[clap(long)]
+    lpi: Option<f64>,
+    /// 将各帧拉伸回光栅图像的宽度
+    #[clap(long, default_value_t = false)]
+    restore_width: bool,
+    /// 拉伸时使用的缩放算法
+    #[clap(long, value_enum, default_value_t = ScaleAlgorithm::Nearest)]
+    scale_algorithm: ScaleAlgorithm,
+    /// 输出文件，各帧按 <输出文件名>_<序号>.tif 写入输出文件所在目录
+    #[clap(short, long)]
+    output: String,
+}
+
+/// 将光栅图像按条纹拆回各帧
+pub fn run_deinterlace(args: &DeinterlaceArgs) -> anyhow::Result<()> {
+    let mut counts = args.count.clone().unwrap_or_else(|| vec![1]);
+    match args.frames {
+        Some(frames) if counts.len() == 1 => counts = vec![counts[0]; frames],
+        Some(frames) if counts.len() != frames => {
+            return Err(anyhow::anyhow!("帧数与 --count 的参数数量不一致"));
+        }
+        _ => {}
+    }
+    if counts.len() < 2 {
+        return Err(anyhow::anyhow!("光栅内至少需要 2 帧"));
+    }
+
+    let Some(mut decoder) = open_decoders(&args.input, None)?.into_iter().next() else {
+        return Err(anyhow::anyhow!("{} 中没有图像", args.input));
+    };
+    let params = decoder
+        .read_params(false)
+        .context(format!("读取文件 {} 失败", args.input))?;
+    info!(
+        "光栅图像：{}x{} 像素，{:?}",
+        params.width, params.height, params.color_type
+    );
+    info!("光栅内各帧的列数：{:?}", counts);
+
+    match params.color_type {
+        Some(tiff::ColorType::CMYK(8)) => {
+            split::<Cmyk8Color>(&mut decoder, args, &counts, lenticular::write_tiff_cmyk8)
+        }
+        Some(tiff::ColorType::CMYK(16)) => {
+            split::<Cmyk16Color>(&mut decoder, args, &counts, lenticular::write_tiff_cmyk16)
+        }
+        Some(tiff::ColorType::RGB(8)) => {
+            split::<Rgb8Color>(&mut decoder, args, &counts, lenticular::write_tiff_rgb8)
+        }
+        Some(tiff::ColorType::RGB(16)) => {
+            split::<Rgb16Color>(&mut decoder, args, &counts, lenticular::write_tiff_rgb16)
+        }
+        other => Err(anyhow::anyhow!(
+            "不支持的颜色类型：{:?}，仅接受 CMYK 8/16位、RGB 8/16位图像",
+            other
+        )),
+    }
+}
+
+fn split<C>(
+    decoder: &mut Box<dyn FrameDecoder>,
+    args: &DeinterlaceArgs,
+    lenticular_widths: &[u32],
+    write: WriteFn<C>,
+) -> anyhow::Result<()>
+where
+    C: InterlaceColor,
+{
+    let img = read_image::<C, _>(decoder).context(format!("读取文件 {} 失败", args.input))?;
+    let pitch = lenticular_widths.iter().sum::<u32>();
+    if img.width() % pitch != 0 {
+        warn!(
+            "图像宽度 {} 像素不是光栅宽度 {} 像素的整数倍，最后一个光栅不完整",
+            img.width(),
+            pitch
+        );
+    }
+    if let (Some(lpi), Some(info)) = (args.lpi, img.info()) {
+        let expected = info.dpi_w / lpi;
+        // 输出 DPI 按成品宽度微调，只检查取整后的光栅宽度
+        if expected.round() as u32 != pitch {
+            warn!(
+                "按 {:.2} DPI 与 {} LPI 计算的光栅宽度为 {:.3} 像素，与各帧列数之和 {} 不一致",
+                info.dpi_w, lpi, expected, pitch
+            );
+        }
+    }
+
+    let restore = args.restore_width.then(|| args.scale_algorithm.into());
+    let frames = deinterlace(&img, lenticular_widths, restore)?;
+    let output = Path::new(&args.output);
+    let stem = output
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("frame");
+    for (index, frame) in frames.iter().enumerate() {
+        let path = output.with_file_name(format!("{}_{:02}.tif", stem, index + 1));
+        let file = File::create(&path).context(format!("创建文件 {:?} 失败", path))?;
+        write(BufWriter::new(file), frame)?;
+        info!(
+            "第 {} 帧已写入 {:?}（{}x{}）",
+            index + 1,
+            path,
+            frame.width(),
+            frame.height()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use anyhow::Context;
+use clap::Args;
+use lenticular_core::{decoder::FrameDecoder, units::Length};
+use log::info;
+
+use crate::{
+    args::{JobArgs, ScreenArgs, SourceArgs},
+    job::Job,
+    log_moire_report, open_decoders, prepare, Prepared,
+};
+
+/// plan 子命令
+#[derive(Debug, Args)]
+pub struct PlanArgs {
+    #[command(flatten)]
+    job: JobArgs,
+    #[command(flatten)]
+    pub screen: ScreenArgs,
+}
+
+/// 列出输入文件各帧的颜色类型、尺寸、分辨率与 ICC 配置文件
+pub fn info(args: &SourceArgs) -> anyhow::Result<()> {
+    for path in &args.input {
+        let mut decoders = open_decoders(path, args.animation_frames)?;
+        info!("{}：共 {} 帧", path, decoders.len());
+        for (index, decoder) in decoders.iter_mut().enumerate() {
+            let params = decoder
+                .read_params(true)
+                .context(format!("读取文件 {} 失败", path))?;
+            let resolution = match params.dpi() {
+                Some((dpi_w, dpi_h)) => format!(
+                    "{:.2}x{:.2} DPI，{:.1}x{:.1} mm",
+                    dpi_w,
+                    dpi_h,
+                    Length::from_px(params.width as f64, dpi_w).to_mm(),
+                    Length::from_px(params.height as f64, dpi_h).to_mm()
+                ),
+                None => "未记录分辨率".to_string(),
+            };
+            let icc_profile = match &params.icc_profile {
+                Some(icc_profile) => format!("ICC 配置文件 {} 字节", icc_profile.len()),
+                None => "无 ICC 配置文件".to_string(),
+            };
+            info!(
+                "第 {} 帧：{:?}，{}x{} 像素，{}，{}",
+                index + 1,
+                params.color_type,
+                params.width,
+                params.height,
+                resolution,
+                icc_profile
+            );
+        }
+    }
+    Ok(())
+}
+
+/// 计算并显示输出版面与龟纹风险，只读取输入文件的元数据
+pub fn plan(args: &PlanArgs) -> anyhow::Result<()> {
+    // 不写出文件，任务的输出路径留空
+    let job = Job::from_args(&args.job, String::new())?;
+    let Prepared { output_info, .. } = prepare(&job, None)?;
+    let layout = &output_info.layout;
+
+    info!("");
+    info!("版面：");
+    info!("颜色类型：{:?}", output_info.color_type());
+    let pitch = layout.lenticular_widths.iter().sum::<u32>();
+    info!(
+        "光栅节距：{} 像素，{:.4} mm",
+        pitch,
+        Length::from_px(pitch as f64, output_info.dpi_w).to_mm()
+    );
+    info!(
+        "光栅数：{}（成品 {}）",
+        output_info.width / pitch,
+        layout.trim_width / pitch
+    );
+    info!("帧数：{}", layout.lenticular_widths.len());
+    info!(
+        "光栅内各帧的列数（从左到右）：{:?}",
+        layout.lenticular_widths
+    );
+    if layout.reverse_print {
+        info!("背面印刷：各帧镜像，光栅内的帧顺序与输入相反");
+    }
+    info!("成品：{}x{} 像素", layout.trim_width, layout.trim_height);
+    if layout.slug() > 0 {
+        info!(
+            "CMYK 8位输出含标记区：{}x{} 像素",
+            output_info.width + layout.slug() * 2,
+            output_info.height + layout.slug() * 2
+        );
+    }
+    log_moire_report(&args.screen, job.lpi, &output_info)
+}
@@ -0,0 +1,466 @@
+use std::path::Path;
+
+use anyhow::Context;
+use lenticular_core::{
+    decoder::FrameDecoder,
+    image::{CmykSeparation, Rgb8Color},
+    lenticular::{FitMode, ImageOptions, InputImageContext, ProcessOptions},
+    units::Length,
+};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{args::JobArgs, open_decoders, AnchorArg, BlackGenerationMode, FitArg, ScaleAlgorithm};
+
+/// 任务文件：描述一次光栅合成的全部输入与输出参数
+///
+/// 字段与 `ProcessOptions`/`ImageOptions` 一一对应，可保存为 TOML 或 JSON，
+/// 用于日后按完全相同的参数重新生成输出。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    /// 输入文件，按光栅内的帧顺序排列
+    pub inputs: Vec<JobInput>,
+    /// 光栅线宽，单位：光栅数/英寸(LPI)
+    pub lpi: f64,
+    /// 输出图像宽度，如 `"85.6mm"`
+    #[serde(default, with = "length")]
+    pub width: Option<Length>,
+    /// 输出图像高度
+    #[serde(default, with = "length")]
+    pub height: Option<Length>,
+    #[serde(default)]
+    pub fit: FitArg,
+    #[serde(default)]
+    pub anchor: AnchorArg,
+    /// 完整显示模式下的背景色，格式：RRGGBB
+    #[serde(default = "default_background", with = "rgb8")]
+    pub background: Rgb8Color,
+    #[serde(default)]
+    pub scale_algorithm: ScaleAlgorithm,
+    #[serde(default, with = "length")]
+    pub bleed: Option<Length>,
+    #[serde(default, with = "length")]
+    pub safe_margin: Option<Length>,
+    #[serde(default)]
+    pub crop_marks: bool,
+    #[serde(default)]
+    pub registration_bars: bool,
+    #[serde(default)]
+    pub reverse_print: bool,
+    /// RGB 输入分色为 CMYK 的参数，不设置时保持输入颜色
+    #[serde(default)]
+    pub cmyk: Option<JobSeparation>,
+    /// 嵌入输出图像的 ICC 配置文件路径
+    #[serde(default)]
+    pub icc_profile: Option<String>,
+    /// 使用 LZW 压缩输出 Tiff 图像
+    #[serde(default)]
+    pub lzw: bool,
+    /// 16位输入时，将输出转换为 8位
+    #[serde(default)]
+    pub to_8bit: bool,
+    #[serde(default)]
+    pub dither: bool,
+    /// 输出文件
+    pub output: String,
+}
+
+/// 一个输入文件及其每帧的光栅宽度
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobInput {
+    pub path: String,
+    /// 每帧在一个光栅内占用的像素列数
+    #[serde(default = "default_strip_width")]
+    pub strip_width: u32,
+    /// 动画输入按等间隔抽取到的帧数，不设置时使用全部帧
+    #[serde(default)]
+    pub animation_frames: Option<usize>,
+}
+
+/// 分色参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobSeparation {
+    #[serde(default)]
+    pub black_generation: BlackGenerationMode,
+    /// 黑版替代灰成分的比例，范围 0~1
+    #[serde(default = "default_black_amount")]
+    pub black_amount: f64,
+    /// 总墨量上限，单位：百分比(%)
+    #[serde(default = "default_total_ink")]
+    pub total_ink: f64,
+}
+
+fn default_background() -> Rgb8Color {
+    Rgb8Color {
+        r: 255,
+        g: 255,
+        b: 255,
+    }
+}
+
+fn default_strip_width() -> u32 {
+    1
+}
+
+fn default_black_amount() -> f64 {
+    0.7
+}
+
+fn default_total_ink() -> f64 {
+    300.0
+}
+
+impl Job {
+    /// 读取任务文件，扩展名为 .json 时按 JSON 解析，否则按 TOML 解析
+    ///
+    /// 文件中的相对路径相对于任务文件所在目录。
+    pub fn load(path: &str) -> anyhow::Result<Job> {
+        let text = std::fs::read_to_string(path).context(format!("读取任务文件 {} 失败", path))?;
+        let mut job: Job = if is_json(path) {
+            serde_json::from_str(&text).context(format!("解析任务文件 {} 失败", path))?
+        } else {
+            toml::from_str(&text).context(format!("解析任务文件 {} 失败", path))?
+        };
+        let dir = job_dir(path);
+        job.map_paths(|file| dir.join(file).to_string_lossy().into_owned());
+        Ok(job)
+    }
+
+    /// 保存任务文件，格式同 [`Job::load`]
+    ///
+    /// 位于任务文件所在目录下的文件保存为相对路径，其余保存为绝对路径。
+    pub fn save(&self, path: &str) -> anyhow::Result<()> {
+        let cwd = std::env::current_dir().context("读取当前目录失败")?;
+        let dir = cwd.join(job_dir(path));
+        let mut job = self.clone();
+        job.map_paths(|file| {
+            let file = cwd.join(file);
+            file.strip_prefix(&dir)
+                .unwrap_or(&file)
+                .to_string_lossy()
+                .into_owned()
+        });
+        let text = if is_json(path) {
+            serde_json::to_string_pretty(&job)?
+        } else {
+            toml::to_string_pretty(&job)?
+        };
+        std::fs::write(path, text).context(format!("写入任务文件 {} 失败", path))?;
+        Ok(())
+    }
+
+    /// 转换输入、输出与 ICC 配置文件的路径
+    fn map_paths<F: Fn(&str) -> String>(&mut self, map: F) {
+        for input in &mut self.inputs {
+            input.path = map(&input.path);
+        }
+        if let Some(icc_profile) = &mut self.icc_profile {
+            *icc_profile = map(icc_profile);
+        }
+        self.output = map(&self.output);
+    }
+
+    /// 由命令行参数生成任务，采用数量只有一个时所有文件都使用该值
+    pub fn from_args(args: &JobArgs, output: String) -> anyhow::Result<Job> {
+        let input = &args.source.input;
+        if input.is_empty() {
+            return Err(anyhow::anyhow!("输入文件为空"));
+        }
+        let mut counts = args.count.clone().unwrap_or_else(|| vec![1]);
+        if counts.len() > 1 && input.len() != counts.len() {
+            return Err(anyhow::anyhow!("输入文件数量与 --count 的参数数量不一致"));
+        }
+        if counts.len() == 1 && input.len() > 1 {
+            // 若只有一个光栅宽度，则所有文件都使用该值
+            counts = vec![counts[0]; input.len()];
+        }
+
+        let (lens, layout, color) = (&args.lens, &args.layout, &args.color);
+        Ok(Job {
+            inputs: input
+                .iter()
+                .zip(counts)
+                .map(|(path, strip_width)| JobInput {
+                    path: path.clone(),
+                    strip_width,
+                    animation_frames: args.source.animation_frames,
+                })
+                .collect(),
+            lpi: lens.lpi,
+            width: lens.output_width,
+            height: lens.output_height,
+            fit: layout.fit,
+            anchor: layout.anchor,
+            background: layout.background,
+            scale_algorithm: args.scale_algorithm,
+            bleed: layout.bleed,
+            safe_margin: layout.safe_margin,
+            crop_marks: layout.crop_marks,
+            registration_bars: layout.registration_bars,
+            reverse_print: layout.reverse_print,
+            cmyk: color.cmyk.then_some(JobSeparation {
+                black_generation: color.black_generation,
+                black_amount: color.black_amount,
+                total_ink: color.total_ink,
+            }),
+            icc_profile: color.icc_profile.clone(),
+            lzw: args.lzw,
+            to_8bit: args.to_8bit,
+            dither: args.dither,
+            output,
+        })
+    }
+
+    /// 检查参数范围
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.inputs.is_empty() {
+            return Err(anyhow::anyhow!("输入文件为空"));
+        }
+        if self.inputs.iter().any(|input| input.strip_width == 0) {
+            return Err(anyhow::anyhow!("重复次数必须大于0"));
+        }
+        if !self.lpi.is_finite() || self.lpi <= 0.0 {
+            return Err(anyhow::anyhow!("LPI必须大于0"));
+        }
+        if self.width.is_some_and(|width| width.to_mm() <= 0.0) {
+            return Err(anyhow::anyhow!("输出图像宽度必须大于0"));
+        }
+        if self.height.is_some_and(|height| height.to_mm() <= 0.0) {
+            return Err(anyhow::anyhow!("输出图像高度必须大于0"));
+        }
+        if self.bleed.is_some_and(|bleed| bleed.to_mm() < 0.0) {
+            return Err(anyhow::anyhow!("出血宽度不能为负"));
+        }
+        if self.safe_margin.is_some_and(|margin| margin.to_mm() < 0.0) {
+            return Err(anyhow::anyhow!("安全边距不能为负"));
+        }
+        if let Some(cmyk) = &self.cmyk {
+            if !(0.0..=1.0).contains(&cmyk.black_amount) {
+                return Err(anyhow::anyhow!("黑版比例必须在 0~1 之间"));
+            }
+            if !(0.0..=400.0).contains(&cmyk.total_ink) {
+                return Err(anyhow::anyhow!("总墨量上限必须在 0~400 之间"));
+            }
+        }
+        if self.dither && !self.to_8bit {
+            return Err(anyhow::anyhow!("抖动仅在转换为 8位时有效"));
+        }
+        Ok(())
+    }
+
+    /// 转换为全局选项，ICC 配置文件在此读取
+    pub fn process_options(&self) -> anyhow::Result<ProcessOptions> {
+        let opt = match (self.width, self.height) {
+            (Some(width), Some(height)) => ProcessOptions::from_dimensions(self.lpi, width, height),
+            (Some(width), None) => ProcessOptions::new(self.lpi, width),
+            (None, Some(height)) => ProcessOptions::from_height(self.lpi, height),
+            (None, None) => return Err(anyhow::anyhow!("输出图像宽度与高度至少指定一项")),
+        };
+        let mut opt = opt
+            .with_scale_algorithm(self.scale_algorithm.into())
+            .with_fit_mode(match self.fit {
+                FitArg::Stretch => FitMode::Stretch,
+                FitArg::Fill => FitMode::Fill {
+                    anchor: self.anchor.into(),
+                },
+                FitArg::Fit => FitMode::Fit {
+                    background: self.background,
+                },
+            })
+            .with_crop_marks(self.crop_marks)
+            .with_registration_bars(self.registration_bars)
+            .with_reverse_print(self.reverse_print)
+            .with_lzw(self.lzw);
+        if let Some(bleed) = self.bleed {
+            opt = opt.with_bleed(bleed);
+        }
+        if let Some(safe_margin) = self.safe_margin {
+            opt = opt.with_safe_margin(safe_margin);
+        }
+        if let Some(cmyk) = &self.cmyk {
+            opt = opt.with_cmyk_separation(CmykSeparation {
+                black_generation: cmyk.black_generation.with_amount(cmyk.black_amount),
+                total_ink_limit: cmyk.total_ink,
+            });
+        }
+        if let Some(icc_profile) = &self.icc_profile {
+            let icc_profile = std::fs::read(icc_profile)
+                .context(format!("读取 ICC 配置文件 {} 失败", icc_profile))?;
+            opt = opt.with_icc_profile(icc_profile);
+        }
+        Ok(opt)
+    }
+
+    /// 打开全部输入文件，多页或动画文件的每一帧共用该文件的光栅宽度
+    pub fn open_inputs(&self) -> anyhow::Result<Vec<InputImageContext<Box<dyn FrameDecoder>>>> {
+        self.open_inputs_with(|input| open_decoders(&input.path, input.animation_frames))
+    }
+
+    /// 同 [`Job::open_inputs`]，由 `open` 提供每个输入文件的各帧解码器
+    pub fn open_inputs_with<F>(
+        &self,
+        mut open: F,
+    ) -> anyhow::Result<Vec<InputImageContext<Box<dyn FrameDecoder>>>>
+    where
+        F: FnMut(&JobInput) -> anyhow::Result<Vec<Box<dyn FrameDecoder>>>,
+    {
+        let mut inputs = vec![];
+        for input in &self.inputs {
+            let decoders = open(input)?;
+            if decoders.len() > 1 {
+                info!("{} 包含 {} 帧", input.path, decoders.len());
+            }
+            inputs.extend(decoders.into_iter().map(|decoder| {
+                InputImageContext::new(
+                    decoder,
+                    ImageOptions {
+                        lenticular_width_px: input.strip_width,
+                    },
+                )
+            }));
+        }
+        Ok(inputs)
+    }
+}
+
+/// 任务文件所在目录，相对路径以此为基准
+fn job_dir(path: &str) -> &Path {
+    Path::new(path).parent().unwrap_or(Path::new(""))
+}
+
+fn is_json(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// 长度按带单位的字符串读写，如 `"85.6mm"`
+mod length {
+    use lenticular_core::units::Length;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        value: &Option<Length>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(length) => serializer.collect_str(length),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Length>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|value| value.parse().map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+/// 颜色按 RRGGBB 字符串读写
+mod rgb8 {
+    use lenticular_core::image::Rgb8Color;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Rgb8Color, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!(
+            "{:02x}{:02x}{:02x}",
+            color.r, color.g, color.b
+        ))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rgb8Color, D::Error> {
+        crate::parse_rgb8(&String::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_file() {
+        let job: Job = toml::from_str(
+            r#"
+            lpi = 75.0
+            width = "85.6mm"
+            fit = "fill"
+            anchor = "top-left"
+            scale_algorithm = "lanczos3"
+            lzw = true
+            output = "card.tif"
+
+            [[inputs]]
+            path = "a.tif"
+            strip_width = 2
+
+            [[inputs]]
+            path = "b.gif"
+            animation_frames = 4
+
+            [cmyk]
+            black_generation = "ucr"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(job.width, Some(Length::mm(85.6)));
+        assert_eq!(job.height, None);
+        assert_eq!(job.inputs[0].strip_width, 2);
+        assert_eq!(job.inputs[1].strip_width, 1);
+        assert_eq!(job.background, default_background());
+        assert_eq!(job.cmyk.as_ref().unwrap().total_ink, 300.0);
+        job.validate().unwrap();
+        job.process_options().unwrap();
+        // 负的出血、安全边距与非有限的 LPI 报错
+        for invalid in [
+            Job {
+                bleed: Some(Length::mm(-3.0)),
+                ..job.clone()
+            },
+            Job {
+                safe_margin: Some(Length::mm(-1.0)),
+                ..job.clone()
+            },
+            Job {
+                lpi: f64::NAN,
+                ..job.clone()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+
+        // TOML 与 JSON 互相转换后保持不变
+        let toml_text = toml::to_string_pretty(&job).unwrap();
+        assert_eq!(toml::from_str::<Job>(&toml_text).unwrap(), job);
+        let json_text = serde_json::to_string_pretty(&job).unwrap();
+        assert_eq!(serde_json::from_str::<Job>(&json_text).unwrap(), job);
+
+        assert!(
+            toml::from_str::<Job>("lpi = 75.0\noutput = \"a.tif\"\ninputs = []\nunknown = 1")
+                .is_err()
+        );
+
+        // 相对路径相对于任务文件所在目录，保存后重新读取路径不变
+        let dir = std::env::temp_dir().join(format!("lenticular-job-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("jobs")).unwrap();
+        let path = dir.join("jobs/card.toml");
+        std::fs::write(&path, toml_text).unwrap();
+        let loaded = Job::load(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            loaded.inputs[0].path,
+            dir.join("jobs/a.tif").to_str().unwrap()
+        );
+        assert_eq!(loaded.output, dir.join("jobs/card.tif").to_str().unwrap());
+        let copy = dir.join("copy.json");
+        loaded.save(copy.to_str().unwrap()).unwrap();
+        let saved = std::fs::read_to_string(&copy).unwrap();
+        assert!(saved.contains("\"jobs/a.tif\""));
+        assert_eq!(Job::load(copy.to_str().unwrap()).unwrap(), loaded);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
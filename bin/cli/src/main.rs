@@ -1,53 +1,249 @@
 use std::{
     fs::{File, OpenOptions},
-    io::BufReader,
+    io::{BufReader, BufWriter},
+    path::Path,
 };
 
 use anyhow::Context;
-use clap::{Parser, ValueEnum};
-use lenticular_core::lenticular::{self, ImageOptions, InputImageContext, ProcessOptions};
-use log::{debug, info};
+use args::{JobArgs, ProofArgs, ScreenArgs, SourceArgs};
+use batch::FrameCache;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use job::Job;
+use lenticular_core::{
+    analysis::{
+        analyze_ink_coverage, analyze_moire, ink_heatmap, limit_ink_coverage, MoireOptions,
+        MoireRisk,
+    },
+    decoder::{read_animation_frames, read_tiff_pages, DynamicFrameDecoder, FrameDecoder},
+    image::{
+        cmyk16_to_cmyk8, rgb16_to_rgb8, shrink_to_width, soft_proof, BlackGeneration, Cmyk16Color,
+        Cmyk8Color, MatrixImage, ProofConversion, ProofOptions, Rgb16Color, Rgb8Color,
+    },
+    lenticular::{
+        self, apply_print_marks, crop_panel, die_cut, impose, plan_panels, process_white_ink_masks,
+        screen_cmyk8, white_ink_from_coverage, Anchor, CutShape, ImpositionOptions,
+        InputImageContext, OutputInfo, PdfOptions, ProcessOptions, Screening, ScreeningOptions,
+        TilingOptions, SEPARATION_NAMES,
+    },
+    units::Length,
+};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+mod args;
+mod batch;
+mod calibrate;
+mod deinterlace;
+mod inspect;
+mod job;
+mod variable;
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
-    // 输入参数
-    /// 输入文件，可以为多个。若输入多个文件，请保证文件数量与后续多个参数数量一致。
-    #[clap(short, long)]
-    input: Vec<String>,
-    /// 指定每个文件的采用数量。
-    ///
-    /// 若输入多个文件，则每个文件对应一个值。
-    ///
-    /// 若该参数只设置一个，则所有文件都使用该值。不输入时，默认为1。
-    #[clap(short, long)]
-    count: Option<Vec<u32>>,
+    #[command(subcommand)]
+    command: Command,
 
-    // 调整
-    /// 缩放算法
-    #[clap(long, default_value = "ScaleAlgorithm::default()")]
-    scale_algorithm: ScaleAlgorithm,
+    /// 启用调试输出
+    #[clap(long, global = true)]
+    debug: bool,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 合成光栅图像，并按参数输出预览、PDF、加网、拼版、白墨与模切等附加文件
+    Interlace(Box<InterlaceArgs>),
+    /// 查看输入文件各帧的颜色类型、尺寸、分辨率与 ICC 配置文件
+    Info(SourceArgs),
+    /// 计算并显示输出版面与龟纹风险，不处理图像数据
+    Plan(Box<inspect::PlanArgs>),
+    /// 合成光栅图像，只输出 sRGB 预览图(PNG)
+    Preview(Box<PreviewArgs>),
+    /// 将光栅图像按条纹拆回各帧
+    Deinterlace(deinterlace::DeinterlaceArgs),
+    /// 生成透镜节距测试图，用于确定光栅板的实际 LPI
+    Calibrate(calibrate::CalibrateArgs),
+    /// 执行任务文件(.toml/.json)
+    Run {
+        /// 任务文件路径
+        job: String,
+    },
+    /// 批量执行任务文件，失败的任务不影响其余任务
+    Batch {
+        /// 任务文件所在目录（按文件名顺序执行其中的 .toml/.json），或列出任务文件的清单
+        ///
+        /// 清单格式同任务文件，如 `jobs = ["a.toml", "b.json"]`，相对路径相对于清单所在目录。
+        path: String,
+        /// 同时执行的任务数
+        #[clap(short, long, default_value_t = 1)]
+        jobs: usize,
+        /// 将各任务的状态与耗时写入该文件(JSON)
+        #[clap(long)]
+        summary: Option<String>,
+    },
+    /// 变量数据：以任务文件为模板，按 CSV 中的每一行生成一个输出
+    ///
+    /// 列名 frameN 替换模板中第 N 个输入文件；与任务字段同名的列（如 lpi、width、output）覆盖该字段；
+    /// 其余列可在 --output-template 中引用。单元格为空时沿用模板中的值。
+    Variable {
+        /// 模板任务文件(.toml/.json)
+        template: String,
+        /// 变量数据文件(CSV)，第一行为列名。frameN 列中的相对路径相对于该文件所在目录。
+        #[clap(long)]
+        data: String,
+        /// 输出文件名模板，{列名} 替换为该行的值，{row} 为从 1 开始的行号，如 out/{row}_{name}.tif
+        #[clap(long)]
+        output_template: Option<String>,
+        /// 同时执行的任务数
+        #[clap(short, long, default_value_t = 1)]
+        jobs: usize,
+        /// 将各行的状态与耗时写入该文件(JSON)
+        #[clap(long)]
+        summary: Option<String>,
+    },
+}
 
-    // 输出参数
-    /// 光栅线宽，单位：光栅数/英寸(LPI)
+/// interlace 子命令：合成参数与各项附加输出
+#[derive(Debug, Args)]
+struct InterlaceArgs {
+    #[command(flatten)]
+    job: JobArgs,
+    /// 输出拼版大图(TIFF)，仅对 CMYK 8位输出有效。需同时指定 --sheet-width 与 --sheet-height。
+    #[clap(long, requires_all = ["sheet_width", "sheet_height"])]
+    impose: Option<String>,
+    /// 拼版纸张宽度，单位同 --output-width
     #[clap(long)]
-    lpi: f64,
-    /// 输出图像宽度，单位：毫米(mm)
+    sheet_width: Option<Length>,
+    /// 拼版纸张高度，单位同 --output-width
     #[clap(long)]
-    output_width: f64,
-    /// （失效）使用 LZW 压缩输出 Tiff 图像。仅当输出文件为 Tiff 格式时有效。
+    sheet_height: Option<Length>,
+    /// 拼版成品间距，单位同 --output-width。水平方向向上取整到整数个光栅。
+    #[clap(long, default_value = "3mm")]
+    gutter: Length,
+    /// 拼版纸张边距，单位同 --output-width
+    #[clap(long, default_value = "10mm")]
+    sheet_margin: Length,
+    /// 拼版数量，不输入时排满整张纸
+    #[clap(long)]
+    impose_copies: Option<usize>,
+    /// 大幅面分块的单块最大宽度，单位同 --output-width。需同时指定 --panel-height。
+    ///
+    /// 分块按 <输出文件名>_r<行>c<列>.tif 写入输出文件所在目录，仅对 CMYK 8位输出有效。
+    #[clap(long, requires = "panel_height")]
+    panel_width: Option<Length>,
+    /// 大幅面分块的单块最大高度，单位同 --output-width
+    #[clap(long, requires = "panel_width")]
+    panel_height: Option<Length>,
+    /// 相邻分块的重叠宽度，单位同 --output-width
+    #[clap(long, default_value = "0mm")]
+    panel_overlap: Length,
+    /// 按总墨量生成白墨通道：总墨量超过该百分比的像素铺白墨，小于 0 时整版铺白。仅对 CMYK 8位输出有效。
+    #[clap(
+        long,
+        allow_negative_numbers = true,
+        conflicts_with = "white_mask",
+        group = "white_source"
+    )]
+    white_threshold: Option<f64>,
+    /// 逐帧的白墨蒙版文件，白色表示铺白墨。展开后的帧数需与输入帧数一致，各蒙版尺寸需与对应的帧一致，按与彩色数据相同的适配方式与列映射合成。
+    #[clap(long, group = "white_source")]
+    white_mask: Vec<String>,
+    /// 将白墨通道单独写入该文件（8位灰度 TIFF），不指定时作为第 5 个通道写入输出文件。
+    ///
+    /// 需同时指定 --white-threshold 或 --white-mask。
+    #[clap(long, requires = "white_source")]
+    white_output: Option<String>,
+    /// 另外输出 PDF，仅对 CMYK 8位输出有效。页面的 TrimBox/BleedBox 与成品及出血一致。
+    ///
+    /// 嵌入 CMYK ICC 配置文件时按 PDF/X-3:2002 输出，配置文件作为输出意图。
+    #[clap(long)]
+    pdf: Option<String>,
+    /// PDF 中的图像不压缩
+    #[clap(long, default_value_t = false)]
+    pdf_uncompressed: bool,
+    /// 输出与图像对齐的模切路径(SVG)
+    #[clap(long)]
+    cut_svg: Option<String>,
+    /// 输出与图像对齐的模切路径(DXF)
+    #[clap(long)]
+    cut_dxf: Option<String>,
+    /// 模切路径的圆角半径，单位同 --output-width
+    #[clap(long)]
+    corner_radius: Option<Length>,
+    /// 自定义模切路径：闭合多边形的各顶点，格式：x,y，相对成品左上角，y 轴向下，单位同 --output-width
+    #[clap(long, num_args = 3.., value_parser = parse_point, conflicts_with = "corner_radius")]
+    cut_path: Option<Vec<[Length; 2]>>,
+    /// 将 CMYK 8位输出中总墨量超过该值的像素压低到该值，单位：百分比(%)
+    #[clap(long)]
+    limit_ink: Option<f64>,
+    /// 输出 CMYK 8位总墨量统计
     #[clap(long, default_value_t = false)]
-    lzw: bool,
+    ink_report: bool,
+    /// 输出总墨量热力图(TIFF)，超过 --ink-threshold 的区域标为红色
+    #[clap(long)]
+    ink_heatmap: Option<String>,
+    /// 总墨量统计与热力图的超限阈值，单位：百分比(%)
+    #[clap(long, default_value_t = 300.0)]
+    ink_threshold: f64,
+    /// 输出 sRGB 预览图(PNG)，仅对 CMYK 8位输出有效
+    #[clap(long)]
+    preview: Option<String>,
+    #[command(flatten)]
+    proof: ProofArgs,
+    #[command(flatten)]
+    screen: ScreenArgs,
+    /// 输出龟纹风险分析（光栅节距、输出 DPI、设备分辨率与调幅网之间的拍频）
+    #[clap(long, default_value_t = false)]
+    moire_report: bool,
     /// 输出文件
     #[clap(short, long)]
     output: String,
-
-    /// 启用调试输出
+    /// 将本次合成参数保存为任务文件(.toml/.json)，可用 run 子命令重新执行
+    ///
+    /// 任务文件只记录合成参数，不能与墨量、白墨、PDF、加网、拼版、分块、模切、预览及龟纹分析等附加输出同时使用。
     #[clap(long)]
-    debug: bool,
+    save_job: Option<String>,
 }
 
-#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+impl InterlaceArgs {
+    /// 已指定的附加输出参数，任务文件不记录这些参数
+    fn extra_options(&self) -> Vec<&'static str> {
+        [
+            ("--limit-ink", self.limit_ink.is_some()),
+            ("--ink-report", self.ink_report),
+            ("--ink-heatmap", self.ink_heatmap.is_some()),
+            ("--white-threshold", self.white_threshold.is_some()),
+            ("--white-mask", !self.white_mask.is_empty()),
+            ("--white-output", self.white_output.is_some()),
+            ("--pdf", self.pdf.is_some()),
+            ("--screen", self.screen.screen.is_some()),
+            ("--impose", self.impose.is_some()),
+            ("--panel-width", self.panel_width.is_some()),
+            ("--cut-svg", self.cut_svg.is_some()),
+            ("--cut-dxf", self.cut_dxf.is_some()),
+            ("--preview", self.preview.is_some()),
+            ("--moire-report", self.moire_report),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+}
+
+/// preview 子命令
+#[derive(Debug, Args)]
+struct PreviewArgs {
+    #[command(flatten)]
+    job: JobArgs,
+    #[command(flatten)]
+    proof: ProofArgs,
+    /// 预览图文件(PNG)
+    #[clap(short, long)]
+    output: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 enum ScaleAlgorithm {
     #[default]
     Nearest,
@@ -55,6 +251,133 @@ enum ScaleAlgorithm {
     Lanczos3,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum FitArg {
+    /// 拉伸到输出尺寸，所有输入图像的尺寸必须一致
+    #[default]
+    Stretch,
+    /// 等比缩放填满，超出部分按 --anchor 裁切
+    Fill,
+    /// 等比缩放完整显示，空白部分填充 --background
+    Fit,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum AnchorArg {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl From<AnchorArg> for Anchor {
+    fn from(val: AnchorArg) -> Self {
+        match val {
+            AnchorArg::TopLeft => Anchor::TopLeft,
+            AnchorArg::Top => Anchor::Top,
+            AnchorArg::TopRight => Anchor::TopRight,
+            AnchorArg::Left => Anchor::Left,
+            AnchorArg::Center => Anchor::Center,
+            AnchorArg::Right => Anchor::Right,
+            AnchorArg::BottomLeft => Anchor::BottomLeft,
+            AnchorArg::Bottom => Anchor::Bottom,
+            AnchorArg::BottomRight => Anchor::BottomRight,
+        }
+    }
+}
+
+/// 解析 x,y 格式的坐标
+fn parse_point(value: &str) -> Result<[Length; 2], String> {
+    let parse = |v: &str| v.parse::<Length>().map_err(|e| e.to_string());
+    match value.split_once(',') {
+        Some((x, y)) => Ok([parse(x)?, parse(y)?]),
+        None => Err(format!("无效的坐标：{}，格式：x,y", value)),
+    }
+}
+
+/// 解析 RRGGBB 格式的颜色
+fn parse_rgb8(value: &str) -> Result<Rgb8Color, String> {
+    let hex = value.trim_start_matches('#');
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|v| u8::from_str_radix(v, 16).ok())
+            .ok_or_else(|| format!("无效的颜色：{}", value))
+    };
+    if hex.len() != 6 {
+        return Err(format!("无效的颜色：{}", value));
+    }
+    Ok(Rgb8Color {
+        r: channel(0)?,
+        g: channel(2)?,
+        b: channel(4)?,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum BlackGenerationMode {
+    /// 不生成黑版
+    None,
+    /// 底色去除，仅在暗部生成黑版
+    Ucr,
+    /// 灰成分替代
+    #[default]
+    Gcr,
+}
+
+impl BlackGenerationMode {
+    fn with_amount(self, amount: f64) -> BlackGeneration {
+        match self {
+            BlackGenerationMode::None => BlackGeneration::None,
+            BlackGenerationMode::Ucr => BlackGeneration::Ucr { amount },
+            BlackGenerationMode::Gcr => BlackGeneration::Gcr { amount },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ScreenMode {
+    /// 调频网（误差扩散）
+    Fm,
+    /// 调幅网
+    Am,
+}
+
+impl ScreenMode {
+    fn with_screen(self, frequency: f64, angles: [f64; 4]) -> Screening {
+        match self {
+            ScreenMode::Fm => Screening::ErrorDiffusion,
+            ScreenMode::Am => Screening::Amplitude { frequency, angles },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum PreviewConversion {
+    /// 简单补色公式
+    Naive,
+    /// 典型印刷油墨叠加模型
+    #[default]
+    Ink,
+}
+
+impl From<PreviewConversion> for ProofConversion {
+    fn from(val: PreviewConversion) -> Self {
+        match val {
+            PreviewConversion::Naive => ProofConversion::Naive,
+            PreviewConversion::Ink => ProofConversion::InkModel,
+        }
+    }
+}
+
 impl From<ScaleAlgorithm> for lenticular::ScaleAlgorithm {
     fn from(val: ScaleAlgorithm) -> Self {
         match val {
@@ -78,79 +401,681 @@ fn main() -> anyhow::Result<()> {
         log::set_max_level(log::LevelFilter::Info);
     }
 
-    if cli.input.is_empty() {
-        return Err(anyhow::anyhow!("输入文件为空"));
-    }
-    let mut counts = cli.count.unwrap_or_else(|| vec![1]);
-    if counts.len() > 1 && cli.input.len() != counts.len() {
-        return Err(anyhow::anyhow!("输入文件数量与 --repeat 的参数数量不一致"));
-    }
-    if counts.iter().any(|&w| w == 0) {
-        return Err(anyhow::anyhow!("重复次数必须大于0"));
-    }
-    if counts.len() == 1 && cli.input.len() > 1 {
-        // 若只有一个光栅宽度，则所有文件都使用该值
-        counts = vec![counts[0]; cli.input.len()];
+    match &cli.command {
+        Command::Interlace(args) => {
+            let job = Job::from_args(&args.job, args.output.clone())?;
+            if let Some(path) = &args.save_job {
+                let extra = args.extra_options();
+                if !extra.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "任务文件不记录 {} 等附加输出，不能与 --save-job 同时使用",
+                        extra.join("、")
+                    ));
+                }
+                job.save(path)?;
+                info!("任务文件已写入 {}", path);
+            }
+            interlace(&job, Some(args), None)
+        }
+        Command::Info(args) => inspect::info(args),
+        Command::Plan(args) => inspect::plan(args),
+        Command::Preview(args) => preview(args),
+        Command::Deinterlace(args) => deinterlace::run_deinterlace(args),
+        Command::Calibrate(args) => calibrate::run_calibrate(args),
+        Command::Run { job } => {
+            info!("任务文件：{}", job);
+            interlace(&Job::load(job)?, None, None)
+        }
+        Command::Batch {
+            path,
+            jobs,
+            summary,
+        } => batch::run_batch(path, *jobs, summary.as_deref()),
+        Command::Variable {
+            template,
+            data,
+            output_template,
+            jobs,
+            summary,
+        } => variable::run_variable(
+            template,
+            data,
+            output_template.as_deref(),
+            *jobs,
+            summary.as_deref(),
+        ),
     }
-    if cli.lpi <= 0.0 {
-        return Err(anyhow::anyhow!("LPI必须大于0"));
+}
+
+/// 已打开的输入文件与计算得到的输出版面
+struct Prepared {
+    opt: ProcessOptions,
+    inputs: Vec<InputImageContext<Box<dyn FrameDecoder>>>,
+    output_info: OutputInfo,
+}
+
+/// 合成结果，16位输出已按任务转换为 8位
+enum Interlaced {
+    Cmyk8(MatrixImage<Cmyk8Color>),
+    Cmyk16(MatrixImage<Cmyk16Color>),
+    Rgb8(MatrixImage<Rgb8Color>),
+    Rgb16(MatrixImage<Rgb16Color>),
+}
+
+/// 检查任务参数，打开输入文件并计算输出版面，不处理图像数据
+///
+/// 指定 `cache` 时输入帧从缓存中读取。
+fn prepare(job: &Job, cache: Option<&FrameCache>) -> anyhow::Result<Prepared> {
+    job.validate()?;
+
+    info!("参数输入：");
+    info!(
+        "输入文件：{:?}",
+        job.inputs.iter().map(|i| &i.path).collect::<Vec<_>>()
+    );
+    info!(
+        "文件重复采用数量：{:?}",
+        job.inputs.iter().map(|i| i.strip_width).collect::<Vec<_>>()
+    );
+    info!("LPI：{:?}", job.lpi);
+    if let Some(width) = job.width {
+        info!("输出图像宽度：{}", width);
     }
-    if cli.output_width <= 0.0 {
-        return Err(anyhow::anyhow!("输出图像宽度必须大于0"));
+    if let Some(height) = job.height {
+        info!("输出图像高度：{}", height);
     }
+    info!("适配方式：{:?}", job.fit);
+    info!("缩放算法：{:?}", job.scale_algorithm);
 
-    // 核心功能
-    info!("参数输入：");
-    info!("输入文件：{:?}", cli.input);
-    info!("文件重复采用数量：{:?}", counts);
-    info!("LPI：{:?}", cli.lpi);
-    info!("输出图像宽度：{:?}", cli.output_width);
-    info!("输出文件：{:?}", cli.output);
-    info!("缩放算法：{:?}", cli.scale_algorithm);
-
-    let inputs: anyhow::Result<Vec<InputImageContext<BufReader<File>>>> = cli
-        .input
-        .iter()
-        .zip(counts.iter())
-        .map(|(input, lenticular_width)| {
-            let file = File::open(input).context(format!("打开文件 {} 失败", input))?;
-            let reader = BufReader::new(file);
-            Ok(InputImageContext::new(
-                reader,
-                ImageOptions {
-                    lenticular_width_px: *lenticular_width,
-                },
-            ))
-        })
-        .collect();
-    let mut inputs = inputs?;
+    let mut inputs = match cache {
+        Some(cache) => cache.open_inputs(job)?,
+        None => job.open_inputs()?,
+    };
 
     info!("");
     info!("开始计算输出...");
 
-    let start = std::time::Instant::now();
-
-    let opt = ProcessOptions::new(cli.lpi, cli.output_width)
-        .with_scale_algorithm(cli.scale_algorithm.into())
-        .with_lzw(cli.lzw);
+    let mut opt = job.process_options()?;
+    if let Some(fitted) = cache.and_then(FrameCache::fitted) {
+        opt = opt.with_frame_cache(fitted);
+    }
     let output_info = opt.calc_output_info(&mut inputs)?;
+    info!(
+        "输出图像：{}x{} 像素，{:.1}x{:.1} mm，{:.2} DPI",
+        output_info.width,
+        output_info.height,
+        output_info.physical_width.to_mm(),
+        output_info.physical_height.to_mm(),
+        output_info.dpi_w
+    );
+    if output_info.layout.bleed_x > 0 || output_info.layout.bleed_y > 0 {
+        info!(
+            "出血：左右 {} 像素，上下 {} 像素",
+            output_info.layout.bleed_x, output_info.layout.bleed_y
+        );
+    }
+    match output_info.icc_profile() {
+        Some(icc_profile) => info!("嵌入 ICC 配置文件：{} 字节", icc_profile.len()),
+        None => info!("输入图像未包含 ICC 配置文件"),
+    }
 
     debug!(
         "inputs: {:?}",
         inputs.iter().map(|i| i.image_options()).collect::<Vec<_>>()
     );
 
-    let out = opt.process_tiff_cmyk8(inputs, &output_info, cli.scale_algorithm.into())?;
+    Ok(Prepared {
+        opt,
+        inputs,
+        output_info,
+    })
+}
+
+/// 按输出颜色类型合成光栅图像
+fn process(job: &Job, prepared: Prepared) -> anyhow::Result<Interlaced> {
+    let Prepared {
+        opt,
+        inputs,
+        output_info,
+    } = prepared;
+    let scale_algorithm = job.scale_algorithm.into();
+    let out = match output_info.color_type() {
+        Some(tiff::ColorType::CMYK(8)) => {
+            Interlaced::Cmyk8(opt.process_tiff_cmyk8(inputs, &output_info, scale_algorithm)?)
+        }
+        Some(tiff::ColorType::RGB(8)) => {
+            Interlaced::Rgb8(opt.process_tiff_rgb8(inputs, &output_info, scale_algorithm)?)
+        }
+        Some(tiff::ColorType::CMYK(16)) => {
+            let out = opt.process_tiff_cmyk16(inputs, &output_info, scale_algorithm)?;
+            if job.to_8bit {
+                Interlaced::Cmyk8(cmyk16_to_cmyk8(&out, job.dither))
+            } else {
+                Interlaced::Cmyk16(out)
+            }
+        }
+        Some(tiff::ColorType::RGB(16)) => {
+            let out = opt.process_tiff_rgb16(inputs, &output_info, scale_algorithm)?;
+            if job.to_8bit {
+                Interlaced::Rgb8(rgb16_to_rgb8(&out, job.dither))
+            } else {
+                Interlaced::Rgb16(out)
+            }
+        }
+        other => {
+            return Err(anyhow::anyhow!(
+                "不支持的输入颜色类型：{:?}，仅接受 CMYK 8/16位、RGB 8/16位图像",
+                other
+            ));
+        }
+    };
+    Ok(out)
+}
+
+/// 按任务合成光栅图像；由 interlace 子命令调用时还执行墨量、预览、加网、拼版、白墨与模切等附加输出
+///
+/// 指定 `cache` 时输入帧从缓存中读取。
+fn interlace(
+    job: &Job,
+    args: Option<&InterlaceArgs>,
+    cache: Option<&FrameCache>,
+) -> anyhow::Result<()> {
+    if args.is_some_and(|args| {
+        args.limit_ink
+            .is_some_and(|limit| !(0.0..=400.0).contains(&limit))
+    }) {
+        return Err(anyhow::anyhow!("总墨量上限必须在 0~400 之间"));
+    }
+
+    let start = std::time::Instant::now();
+
+    let prepared = prepare(job, cache)?;
+    info!("输出文件：{:?}", job.output);
+    let output_info = prepared.output_info.clone();
+    if let Some(args) = args.filter(|args| args.moire_report) {
+        log_moire_report(&args.screen, job.lpi, &output_info)?;
+    }
+
+    let create_output = || {
+        OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&job.output)
+    };
+
+    let cmyk8_output = match output_info.color_type() {
+        Some(tiff::ColorType::CMYK(8)) => true,
+        Some(tiff::ColorType::CMYK(16)) => job.to_8bit,
+        _ => false,
+    };
+    if args.is_some_and(|args| args.white_threshold.is_some() || !args.white_mask.is_empty())
+        && !cmyk8_output
+    {
+        return Err(anyhow::anyhow!("白墨通道仅支持 CMYK 8位输出"));
+    }
 
-    let output_file = OpenOptions::new()
-        .create(true)
-        .truncate(true)
-        .write(true)
-        .open(&cli.output)?;
-    lenticular::write_tiff_cmyk8(output_file, &out)?;
+    match process(job, prepared)? {
+        Interlaced::Cmyk8(out) => {
+            let out = finish_cmyk8(args, &output_info, out)?;
+            write_cmyk8(args, &output_info, &out, create_output()?)?;
+        }
+        Interlaced::Cmyk16(out) => lenticular::write_tiff_cmyk16(create_output()?, &out)?,
+        Interlaced::Rgb8(out) => lenticular::write_tiff_rgb8(create_output()?, &out)?,
+        Interlaced::Rgb16(out) => lenticular::write_tiff_rgb16(create_output()?, &out)?,
+    }
+
+    if let Some(args) = args.filter(|args| args.cut_svg.is_some() || args.cut_dxf.is_some()) {
+        let shape = match (&args.cut_path, args.corner_radius) {
+            (Some(points), _) => CutShape::Custom(points.clone()),
+            (None, Some(radius)) => CutShape::RoundedRectangle { radius },
+            (None, None) => CutShape::Rectangle,
+        };
+        // 仅 CMYK 8位输出写出时绘制标记，其他输出没有标记区域
+        let cut = die_cut(&output_info, &shape, cmyk8_output);
+        if let Some(path) = &args.cut_svg {
+            std::fs::write(path, cut.to_svg()).context(format!("写入文件 {} 失败", path))?;
+            info!("模切路径已写入 {}", path);
+        }
+        if let Some(path) = &args.cut_dxf {
+            std::fs::write(path, cut.to_dxf()).context(format!("写入文件 {} 失败", path))?;
+            info!("模切路径已写入 {}", path);
+        }
+    }
 
     let elapsed = start.elapsed().as_millis();
     info!("处理完成，耗时 {} 毫秒", elapsed);
 
     Ok(())
 }
+
+/// 合成光栅图像并只写出 sRGB 预览图，CMYK 输出经软打样转换，RGB 输出直接缩小
+fn preview(args: &PreviewArgs) -> anyhow::Result<()> {
+    let job = Job::from_args(&args.job, args.output.clone())?;
+    let start = std::time::Instant::now();
+
+    let prepared = prepare(&job, None)?;
+    info!("预览图：{:?}", args.output);
+    let preview = match process(&job, prepared)? {
+        Interlaced::Cmyk8(out) => proof_cmyk8(&out, &args.proof)?,
+        Interlaced::Cmyk16(out) => proof_cmyk8(&cmyk16_to_cmyk8(&out, false), &args.proof)?,
+        Interlaced::Rgb8(out) => shrink_to_width(out, args.proof.preview_width)?,
+        Interlaced::Rgb16(out) => {
+            shrink_to_width(rgb16_to_rgb8(&out, false), args.proof.preview_width)?
+        }
+    };
+    write_preview(&args.output, &preview)?;
+
+    let elapsed = start.elapsed().as_millis();
+    info!("处理完成，耗时 {} 毫秒", elapsed);
+    Ok(())
+}
+
+/// CMYK 8位输出的软打样，带印刷标记时先绘制标记
+fn proof_cmyk8(
+    out: &MatrixImage<Cmyk8Color>,
+    proof: &ProofArgs,
+) -> anyhow::Result<MatrixImage<Rgb8Color>> {
+    let marked;
+    let proof_source = match out.layout() {
+        Some(layout) if layout.has_marks() => {
+            marked = apply_print_marks(out, layout);
+            &marked
+        }
+        _ => out,
+    };
+    Ok(soft_proof(
+        proof_source,
+        &ProofOptions {
+            conversion: proof.preview_conversion.into(),
+            max_width: proof.preview_width,
+        },
+    )?)
+}
+
+fn write_preview(path: &str, preview: &MatrixImage<Rgb8Color>) -> anyhow::Result<()> {
+    let file = File::create(path).context(format!("创建文件 {} 失败", path))?;
+    lenticular::write_png_rgb8(BufWriter::new(file), preview)?;
+    info!(
+        "预览图已写入 {}（{}x{}）",
+        path,
+        preview.width(),
+        preview.height()
+    );
+    Ok(())
+}
+
+/// CMYK 8位输出的墨量限制、统计、预览、PDF、加网、拼版与分块
+fn finish_cmyk8(
+    args: Option<&InterlaceArgs>,
+    output_info: &OutputInfo,
+    mut out: MatrixImage<Cmyk8Color>,
+) -> anyhow::Result<MatrixImage<Cmyk8Color>> {
+    let Some(args) = args else {
+        return Ok(out);
+    };
+    if let Some(limit) = args.limit_ink {
+        let changed = limit_ink_coverage(&mut out, limit);
+        info!("总墨量限制到 {}%，调整像素 {} 个", limit, changed);
+    }
+
+    if args.ink_report {
+        let report = analyze_ink_coverage(&out, args.ink_threshold);
+        info!("总墨量统计：");
+        info!("最大值：{:.1}%", report.max);
+        info!("平均值：{:.1}%", report.mean);
+        info!(
+            "P50 / P95 / P99：{:.1}% / {:.1}% / {:.1}%",
+            report.percentile(50.0),
+            report.percentile(95.0),
+            report.percentile(99.0)
+        );
+        info!(
+            "超过 {}% 的像素：{} ({:.3}%)",
+            report.threshold,
+            report.over_threshold,
+            report.over_threshold_ratio() * 100.0
+        );
+    }
+
+    if let Some(path) = &args.ink_heatmap {
+        let heatmap = ink_heatmap(&out, args.ink_threshold);
+        let file = File::create(path).context(format!("创建文件 {} 失败", path))?;
+        lenticular::write_tiff_rgb8(file, &heatmap)?;
+        info!("总墨量热力图已写入 {}", path);
+    }
+
+    if let Some(path) = &args.preview {
+        write_preview(path, &proof_cmyk8(&out, &args.proof)?)?;
+    }
+
+    if let Some(path) = &args.pdf {
+        let title = Path::new(&args.output)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let options = PdfOptions {
+            compress: !args.pdf_uncompressed,
+            title: title.to_string(),
+            ..Default::default()
+        };
+        let file = File::create(path).context(format!("创建文件 {} 失败", path))?;
+        lenticular::write_pdf_cmyk8(BufWriter::new(file), &out, &options)?;
+        info!("PDF 已写入 {}", path);
+    }
+
+    if let Some(mode) = args.screen.screen {
+        let angles = args.screen.angles()?;
+        let options = ScreeningOptions {
+            screening: mode.with_screen(args.screen.screen_frequency, angles),
+            device_dpi: args.screen.screen_dpi,
+            lock_to_lens: args.screen.screen_lock_lens,
+        };
+        let plates = screen_cmyk8(&out, &options)?;
+        let output = Path::new(&args.output);
+        let stem = output
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("screen");
+        for (name, plate) in SEPARATION_NAMES.iter().zip(&plates) {
+            let path = output.with_file_name(format!("{}_{}.tif", stem, name));
+            let file = File::create(&path).context(format!("创建文件 {:?} 失败", path))?;
+            lenticular::write_tiff_bitmap(BufWriter::new(file), plate)?;
+            info!(
+                "{} 分色版已写入 {:?}（{}x{}，{:.0} DPI，着墨 {:.1}%）",
+                name,
+                path,
+                plate.width,
+                plate.height,
+                plate.dpi_w,
+                plate.coverage() * 100.0
+            );
+        }
+    }
+
+    if let (Some(path), Some(sheet_width), Some(sheet_height)) =
+        (&args.impose, args.sheet_width, args.sheet_height)
+    {
+        let options = ImpositionOptions {
+            lpi: args.job.lens.lpi,
+            sheet_width,
+            sheet_height,
+            gutter: args.gutter,
+            margin: args.sheet_margin,
+            copies: args.impose_copies,
+            crop_marks: args.job.layout.crop_marks,
+        };
+        let sheet = impose(std::slice::from_ref(&out), &options)?;
+        let file = File::create(path).context(format!("创建文件 {} 失败", path))?;
+        lenticular::write_tiff_cmyk8(BufWriter::new(file), &sheet)?;
+        info!(
+            "拼版大图已写入 {}（{}x{}，按光栅节距以 {:.3} DPI 排版）",
+            path,
+            sheet.width(),
+            sheet.height(),
+            sheet.info().map_or(0.0, |info| info.dpi_w)
+        );
+    }
+
+    if let (Some(panel_width), Some(panel_height)) = (args.panel_width, args.panel_height) {
+        let options = TilingOptions {
+            panel_width,
+            panel_height,
+            overlap: args.panel_overlap,
+        };
+        let panels = plan_panels(output_info, &options)?;
+        let output = Path::new(&args.output);
+        let stem = output
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("panel");
+        for panel in &panels {
+            let path = output.with_file_name(format!("{}.tif", panel.name(stem)));
+            let file = File::create(&path).context(format!("创建文件 {:?} 失败", path))?;
+            lenticular::write_tiff_cmyk8(BufWriter::new(file), &crop_panel(&out, panel))?;
+            info!(
+                "分块已写入 {:?}（{}x{}，位置 {},{}）",
+                path, panel.width, panel.height, panel.x, panel.y
+            );
+        }
+    }
+
+    Ok(out)
+}
+
+/// 输出龟纹风险分析，调幅网参数取自加网选项
+fn log_moire_report(
+    screen: &ScreenArgs,
+    lens_lpi: f64,
+    output_info: &OutputInfo,
+) -> anyhow::Result<()> {
+    let screen_angles = screen.angles()?;
+    let options = MoireOptions {
+        lens_lpi,
+        printer_dpi: screen.printer_dpi,
+        screen_frequency: matches!(screen.screen, Some(ScreenMode::Am))
+            .then_some(screen.screen_frequency),
+        screen_angles,
+    };
+    let report = analyze_moire(output_info, &options);
+    info!("龟纹风险分析：");
+    for beat in &report.beats {
+        let period = match beat.period {
+            Some(period) => format!("{:.2}mm", period.to_mm()),
+            None => "无".to_string(),
+        };
+        let message = format!(
+            "{}：拍频 {:.3} 周期/英寸，条纹周期 {}，风险 {:?}",
+            beat.source, beat.frequency, period, beat.risk
+        );
+        match beat.risk {
+            MoireRisk::Low => info!("{}", message),
+            _ => warn!("{}", message),
+        }
+    }
+    if !report.suggested_lpi.is_empty() {
+        info!(
+            "建议的光栅线数：{}",
+            report
+                .suggested_lpi
+                .iter()
+                .map(|lpi| format!("{:.3}", lpi))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    if !report.suggested_dpi.is_empty() {
+        info!(
+            "建议的输出 DPI：{}",
+            report
+                .suggested_dpi
+                .iter()
+                .map(|dpi| format!("{:.2}", dpi))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// 写出 CMYK 8位输出文件，指定白墨时按参数合成为第 5 个通道或单独写出
+fn write_cmyk8(
+    args: Option<&InterlaceArgs>,
+    output_info: &OutputInfo,
+    out: &MatrixImage<Cmyk8Color>,
+    writer: File,
+) -> anyhow::Result<()> {
+    let Some(args) = args else {
+        lenticular::write_tiff_cmyk8(writer, out)?;
+        return Ok(());
+    };
+    let white = if let Some(threshold) = args.white_threshold {
+        Some(white_ink_from_coverage(out, threshold))
+    } else if !args.white_mask.is_empty() {
+        let mut masks = vec![];
+        for mask in &args.white_mask {
+            masks.extend(open_decoders(mask, args.job.source.animation_frames)?);
+        }
+        Some(process_white_ink_masks(
+            masks,
+            output_info,
+            args.job.scale_algorithm.into(),
+        )?)
+    } else {
+        None
+    };
+
+    match (white, &args.white_output) {
+        (Some(white), Some(path)) => {
+            lenticular::write_tiff_cmyk8(writer, out)?;
+            let file = File::create(path).context(format!("创建文件 {} 失败", path))?;
+            lenticular::write_tiff_gray8(BufWriter::new(file), &white)?;
+            info!("白墨通道已写入 {}", path);
+        }
+        (Some(white), None) => {
+            lenticular::write_tiff_cmykw8(writer, out, &white)?;
+            info!("白墨通道已作为第 5 个通道写入输出文件");
+        }
+        (None, _) => lenticular::write_tiff_cmyk8(writer, out)?,
+    }
+    Ok(())
+}
+
+/// 按扩展名选择解码器，TIFF 之外的格式交给 `image` 库
+///
+/// TIFF 文件的每一页、动画的每一帧都作为一帧返回。
+fn open_decoders(
+    input: &str,
+    animation_frames: Option<usize>,
+) -> anyhow::Result<Vec<Box<dyn FrameDecoder>>> {
+    let file = File::open(input).context(format!("打开文件 {} 失败", input))?;
+    let reader = BufReader::new(file);
+
+    let extension = Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "tif" | "tiff" => {
+            let pages = read_tiff_pages(reader).context(format!("读取文件 {} 失败", input))?;
+            Ok(pages
+                .into_iter()
+                .map(|page| Box::new(page) as Box<dyn FrameDecoder>)
+                .collect())
+        }
+        "gif" | "png" | "apng" | "webp" => {
+            let frames = read_animation_frames(reader, animation_frames)
+                .context(format!("读取文件 {} 失败", input))?;
+            Ok(frames
+                .into_iter()
+                .map(|frame| Box::new(frame) as Box<dyn FrameDecoder>)
+                .collect())
+        }
+        _ => Ok(vec![Box::new(DynamicFrameDecoder::new(reader))]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_subcommands() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "lenticular",
+            "interlace",
+            "-i",
+            "a.tif",
+            "-i",
+            "b.tif",
+            "-c",
+            "2",
+            "--lpi",
+            "40",
+            "--output-width",
+            "30mm",
+            "--bleed",
+            "3mm",
+            "-o",
+            "out.tif",
+            "--debug",
+        ])
+        .unwrap();
+        assert!(cli.debug);
+        let Command::Interlace(args) = &cli.command else {
+            panic!("应为 interlace 子命令");
+        };
+        let job = Job::from_args(&args.job, args.output.clone()).unwrap();
+        assert_eq!(job.inputs.len(), 2);
+        assert!(job.inputs.iter().all(|input| input.strip_width == 2));
+        assert_eq!(job.bleed, Some(Length::mm(3.0)));
+        assert_eq!(job.output, "out.tif");
+        assert!(args.extra_options().is_empty());
+
+        // plan 与 interlace 共用光栅与物理参数，但不需要输出文件
+        let cli = Cli::try_parse_from([
+            "lenticular",
+            "plan",
+            "-i",
+            "a.tif",
+            "--lpi",
+            "40",
+            "--output-height",
+            "2in",
+            "--screen-angles",
+            "15,75",
+        ])
+        .unwrap();
+        let Command::Plan(args) = &cli.command else {
+            panic!("应为 plan 子命令");
+        };
+        // 网角数量不为 4 时报错，不按默认网角分析
+        assert!(args.screen.angles().is_err());
+        // 宽度与高度至少指定一项
+        assert!(Cli::try_parse_from([
+            "lenticular",
+            "preview",
+            "-i",
+            "a.tif",
+            "--lpi",
+            "40",
+            "-o",
+            "p.png"
+        ])
+        .is_err());
+        assert!(Cli::try_parse_from(["lenticular", "-i", "a.tif", "--lpi", "40"]).is_err());
+        // 单独的白墨输出文件需要指定白墨来源
+        let white_args = |extra: &[&'static str]| {
+            let args = [
+                "lenticular",
+                "interlace",
+                "-i",
+                "a.tif",
+                "--lpi",
+                "40",
+                "--output-width",
+                "30mm",
+                "-o",
+                "out.tif",
+                "--white-output",
+                "w.tif",
+            ];
+            Cli::try_parse_from(args.iter().chain(extra))
+        };
+        assert!(white_args(&[]).is_err());
+        let cli = white_args(&["--white-threshold", "-1"]).unwrap();
+        let Command::Interlace(args) = &cli.command else {
+            panic!("应为 interlace 子命令");
+        };
+        // 任务文件不记录白墨参数
+        assert_eq!(
+            args.extra_options(),
+            ["--white-threshold", "--white-output"]
+        );
+    }
+}
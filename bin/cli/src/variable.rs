@@ -0,0 +1,274 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use anyhow::Context;
+use log::info;
+use serde_json::Value;
+
+use crate::{
+    batch::{run_jobs, FrameCache},
+    job::Job,
+};
+
+/// CSV 列的用途
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    /// `frameN`：替换模板中第 N 个输入文件（从 1 开始）
+    Frame(usize),
+    /// 与任务字段同名：覆盖该字段
+    Override(String),
+    /// 其余列只用于输出文件名模板
+    Data,
+}
+
+/// 变量数据：以任务文件为模板，CSV 中的每一行生成一个输出
+///
+/// 未被 `frameN` 列替换的输入为静态帧，只解码、缩放一次，供所有行复用。
+/// 单元格为空时沿用模板中的值。模板中的相对路径相对于模板文件所在目录，
+/// `frameN` 列中的相对路径相对于 CSV 文件所在目录。
+pub fn run_variable(
+    template: &str,
+    data: &str,
+    output_template: Option<&str>,
+    workers: usize,
+    summary: Option<&str>,
+) -> anyhow::Result<()> {
+    let template = Job::load(template)?;
+    let mut reader = csv::Reader::from_path(data).context(format!("读取文件 {} 失败", data))?;
+    let headers = reader
+        .headers()
+        .context(format!("读取文件 {} 失败", data))?
+        .iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let columns = classify_columns(&template, &headers)?;
+    if output_template.is_none() && !headers.iter().any(|header| header == "output") {
+        return Err(anyhow::anyhow!(
+            "必须指定 --output-template 或在数据中提供 output 列"
+        ));
+    }
+
+    let static_paths = template
+        .inputs
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !columns.contains(&Column::Frame(*index)))
+        .map(|(_, input)| input.path.clone())
+        .collect::<HashSet<_>>();
+    info!("静态输入文件：{:?}", static_paths);
+
+    let data_dir = Path::new(data).parent().unwrap_or(Path::new(""));
+    let mut jobs = vec![];
+    let mut outputs = HashMap::new();
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 1;
+        let job = record
+            .context(format!("读取第 {} 行失败", row))
+            .and_then(|record| {
+                let values = headers
+                    .iter()
+                    .map(String::as_str)
+                    .zip(record.iter())
+                    .collect::<Vec<_>>();
+                record_job(&template, &columns, &values, row, data_dir, output_template)
+            })
+            .and_then(|job| match outputs.insert(job.output.clone(), row) {
+                Some(previous) => Err(anyhow::anyhow!(
+                    "输出文件 {} 与第 {} 行重复",
+                    job.output,
+                    previous
+                )),
+                None => Ok(job),
+            });
+        jobs.push((format!("第 {} 行", row), job));
+    }
+    if jobs.is_empty() {
+        return Err(anyhow::anyhow!("{} 中没有数据", data));
+    }
+
+    let cache = FrameCache::for_static_inputs(static_paths, &jobs);
+    run_jobs(jobs, &cache, workers, summary)
+}
+
+/// 按列名确定各列的用途
+fn classify_columns(template: &Job, headers: &[String]) -> anyhow::Result<Vec<Column>> {
+    let fields = match serde_json::to_value(template)? {
+        Value::Object(fields) => fields,
+        _ => unreachable!(),
+    };
+    headers
+        .iter()
+        .map(|header| {
+            if let Some(index) = header.strip_prefix("frame") {
+                let index = index
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| (1..=template.inputs.len()).contains(index))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "列 {} 无效：模板共有 {} 个输入文件，列名应为 frame1~frame{}",
+                            header,
+                            template.inputs.len(),
+                            template.inputs.len()
+                        )
+                    })?;
+                Ok(Column::Frame(index - 1))
+            } else if header != "inputs" && fields.contains_key(header) {
+                Ok(Column::Override(header.clone()))
+            } else {
+                Ok(Column::Data)
+            }
+        })
+        .collect()
+}
+
+/// 由模板与一行数据生成任务，`values` 为按列顺序排列的列名与值
+///
+/// `frameN` 列中的相对路径相对于 `data_dir`。
+fn record_job(
+    template: &Job,
+    columns: &[Column],
+    values: &[(&str, &str)],
+    row: usize,
+    data_dir: &Path,
+    output_template: Option<&str>,
+) -> anyhow::Result<Job> {
+    let mut fields = serde_json::to_value(template)?;
+    for (column, &(header, value)) in columns.iter().zip(values) {
+        if value.is_empty() {
+            continue;
+        }
+        match column {
+            Column::Frame(index) => {
+                let path = data_dir.join(value).to_string_lossy().into_owned();
+                fields["inputs"][*index]["path"] = Value::String(path);
+            }
+            Column::Override(field) => {
+                // 模板中为数值或布尔值的字段按 JSON 解析，其余按字符串处理
+                fields[field] = match &fields[field] {
+                    Value::Number(_) | Value::Bool(_) => serde_json::from_str(value)
+                        .context(format!("第 {} 行的 {} 无效：{}", row, header, value))?,
+                    _ => Value::String(value.to_string()),
+                };
+            }
+            Column::Data => {}
+        }
+    }
+    if let Some(output_template) = output_template {
+        fields["output"] = Value::String(render_output(output_template, values, row)?);
+    }
+    serde_json::from_value(fields).context(format!("第 {} 行的参数无效", row))
+}
+
+/// 替换输出文件名模板中的 `{列名}`，`{row}` 为从 1 开始的行号
+fn render_output(template: &str, values: &[(&str, &str)], row: usize) -> anyhow::Result<String> {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let name = &rest[start + 1..start + end];
+        output.push_str(&rest[..start]);
+        let value = values
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value);
+        match (name, value) {
+            (_, Some(value)) => output.push_str(value),
+            ("row", None) => output.push_str(&row.to_string()),
+            (_, None) => {
+                return Err(anyhow::anyhow!(
+                    "输出文件名模板中的 {{{}}} 不是数据中的列",
+                    name
+                ))
+            }
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_job() {
+        let template: Job = toml::from_str(
+            r#"
+            lpi = 75.0
+            width = "85.6mm"
+            output = "card.tif"
+
+            [[inputs]]
+            path = "background.tif"
+
+            [[inputs]]
+            path = "photo.tif"
+            strip_width = 2
+            "#,
+        )
+        .unwrap();
+        let headers = ["name", "frame2", "lpi", "width"].map(String::from);
+        let columns = classify_columns(&template, &headers).unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                Column::Data,
+                Column::Frame(1),
+                Column::Override("lpi".to_string()),
+                Column::Override("width".to_string()),
+            ]
+        );
+        assert!(classify_columns(&template, &["frame3".to_string()]).is_err());
+
+        let values = [
+            ("name", "alice"),
+            ("frame2", "photos/alice.tif"),
+            ("lpi", "75.2"),
+            ("width", ""),
+        ];
+        let data_dir = Path::new("data");
+        let job = record_job(
+            &template,
+            &columns,
+            &values,
+            1,
+            data_dir,
+            Some("out/{row}_{name}.tif"),
+        )
+        .unwrap();
+        assert_eq!(job.inputs[0].path, "background.tif");
+        // 数据中的相对路径相对于 CSV 文件所在目录
+        assert_eq!(
+            Path::new(&job.inputs[1].path),
+            data_dir.join("photos/alice.tif")
+        );
+        assert_eq!(job.inputs[1].strip_width, 2);
+        assert_eq!(job.lpi, 75.2);
+        // 空单元格沿用模板
+        assert_eq!(job.width, template.width);
+        assert_eq!(job.output, "out/1_alice.tif");
+
+        assert!(record_job(
+            &template,
+            &columns,
+            &values,
+            1,
+            data_dir,
+            Some("{unknown}.tif")
+        )
+        .is_err());
+        let values = [
+            ("name", "bob"),
+            ("frame2", ""),
+            ("lpi", "abc"),
+            ("width", ""),
+        ];
+        assert!(record_job(&template, &columns, &values, 2, data_dir, None).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use crate::image::{Cmyk8Color, MatrixImage, Rgb8Color};
+
+/// 总墨量直方图的最大值（4 × 255）
+const MAX_TOTAL: usize = 4 * 255;
+
+/// 总墨量（C+M+Y+K）统计报告，墨量单位均为百分比（0~400）
+#[derive(Debug, Clone)]
+pub struct InkCoverageReport {
+    pub max: f64,
+    pub mean: f64,
+    /// 超限阈值
+    pub threshold: f64,
+    /// 超过阈值的像素数量
+    pub over_threshold: u64,
+    pub total_pixels: u64,
+    histogram: Vec<u64>,
+}
+
+impl InkCoverageReport {
+    /// 总墨量的百分位数，`p` 范围 0~100
+    pub fn percentile(&self, p: f64) -> f64 {
+        let rank = ((p.clamp(0.0, 100.0) / 100.0) * self.total_pixels as f64).ceil() as u64;
+        let rank = rank.max(1);
+
+        let mut count = 0;
+        for (total, n) in self.histogram.iter().enumerate() {
+            count += n;
+            if count >= rank {
+                return total_to_percent(total);
+            }
+        }
+        self.max
+    }
+
+    /// 超过阈值的像素占比，范围 0~1
+    pub fn over_threshold_ratio(&self) -> f64 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+        self.over_threshold as f64 / self.total_pixels as f64
+    }
+}
+
+/// 统计图像的总墨量分布，`threshold` 为超限阈值（百分比）
+pub fn analyze_ink_coverage(img: &MatrixImage<Cmyk8Color>, threshold: f64) -> InkCoverageReport {
+    let mut histogram = vec![0u64; MAX_TOTAL + 1];
+    let mut sum = 0u64;
+    for px in img.inner().iter() {
+        let total = total_ink(px);
+        histogram[total] += 1;
+        sum += total as u64;
+    }
+
+    let total_pixels = img.inner().len() as u64;
+    let max = histogram.iter().rposition(|&n| n > 0).unwrap_or(0);
+    let over_threshold = histogram
+        .iter()
+        .enumerate()
+        .filter(|(total, _)| total_to_percent(*total) > threshold)
+        .map(|(_, n)| n)
+        .sum();
+
+    InkCoverageReport {
+        max: total_to_percent(max),
+        mean: if total_pixels == 0 {
+            0.0
+        } else {
+            sum as f64 / total_pixels as f64 / 255.0 * 100.0
+        },
+        threshold,
+        over_threshold,
+        total_pixels,
+        histogram,
+    }
+}
+
+/// 生成总墨量热力图
+///
+/// 未超限区域按总墨量显示为灰度（越深墨量越大），超限区域显示为红色，超出越多越亮。
+pub fn ink_heatmap(img: &MatrixImage<Cmyk8Color>, threshold: f64) -> MatrixImage<Rgb8Color> {
+    let mut out = MatrixImage::new(img.width(), img.height());
+    out.inner_mut()
+        .iter_mut()
+        .zip(img.inner().iter())
+        .for_each(|(dst, px)| {
+            let percent = total_to_percent(total_ink(px));
+            *dst = if percent > threshold {
+                let excess = ((percent - threshold) / (400.0 - threshold).max(1.0)).min(1.0);
+                let level = (160.0 + 95.0 * excess) as u8;
+                Rgb8Color {
+                    r: level,
+                    g: 0,
+                    b: 0,
+                }
+            } else {
+                let level = (255.0 - percent / 400.0 * 200.0) as u8;
+                Rgb8Color {
+                    r: level,
+                    g: level,
+                    b: level,
+                }
+            };
+        });
+    if let Some(info) = img.info() {
+        out.set_info(info.clone());
+    }
+    out.set_lzw(img.lzw());
+    out
+}
+
+/// 将总墨量超过 `target` （百分比）的像素按比例压低 CMY，保留黑版
+///
+/// 返回被修改的像素数量。
+pub fn limit_ink_coverage(img: &mut MatrixImage<Cmyk8Color>, target: f64) -> u64 {
+    let limit = (target / 100.0 * 255.0).floor().max(0.0) as usize;
+    let mut changed = 0;
+
+    for px in img.inner_mut().iter_mut() {
+        let total = total_ink(px);
+        if total <= limit {
+            continue;
+        }
+
+        if px.k as usize >= limit {
+            *px = Cmyk8Color {
+                c: 0,
+                m: 0,
+                y: 0,
+                k: limit as u8,
+            };
+        } else {
+            // 向下取整，保证结果不超过上限
+            let scale = (limit - px.k as usize) as f64 / (total - px.k as usize) as f64;
+            px.c = (px.c as f64 * scale).floor() as u8;
+            px.m = (px.m as f64 * scale).floor() as u8;
+            px.y = (px.y as f64 * scale).floor() as u8;
+        }
+        changed += 1;
+    }
+
+    changed
+}
+
+fn total_ink(px: &Cmyk8Color) -> usize {
+    px.c as usize + px.m as usize + px.y as usize + px.k as usize
+}
+
+fn total_to_percent(total: usize) -> f64 {
+    total as f64 / 255.0 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ink_coverage() {
+        let mut img: MatrixImage<Cmyk8Color> = MatrixImage::new(10, 10);
+        // 前 90 个像素为 100% 黑，后 10 个像素为四色 100%
+        img.inner_mut().iter_mut().enumerate().for_each(|(i, px)| {
+            *px = if i < 90 {
+                Cmyk8Color {
+                    c: 0,
+                    m: 0,
+                    y: 0,
+                    k: 255,
+                }
+            } else {
+                Cmyk8Color {
+                    c: 255,
+                    m: 255,
+                    y: 255,
+                    k: 255,
+                }
+            }
+        });
+
+        let report = analyze_ink_coverage(&img, 300.0);
+        assert_eq!(report.max, 400.0);
+        assert_eq!(report.over_threshold, 10);
+        assert_eq!(report.percentile(50.0), 100.0);
+        assert_eq!(report.percentile(95.0), 400.0);
+        assert!((report.mean - 130.0).abs() < 1e-9);
+
+        let heatmap = ink_heatmap(&img, 300.0);
+        assert_eq!(heatmap.inner()[[9, 9]].g, 0);
+        assert!(heatmap.inner()[[0, 0]].g > 0);
+
+        assert_eq!(limit_ink_coverage(&mut img, 280.0), 10);
+        let report = analyze_ink_coverage(&img, 280.0);
+        assert!(report.max <= 280.0);
+        assert_eq!(img.inner()[[9, 9]].k, 255);
+        assert_eq!(report.over_threshold, 0);
+    }
+}
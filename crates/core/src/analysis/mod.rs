@@ -0,0 +1,5 @@
+mod ink;
+mod moire;
+
+pub use ink::{analyze_ink_coverage, ink_heatmap, limit_ink_coverage, InkCoverageReport};
+pub use moire::{analyze_moire, MoireBeat, MoireOptions, MoireReport, MoireRisk};
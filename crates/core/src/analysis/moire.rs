@@ -0,0 +1,314 @@
+use crate::{lenticular::OutputInfo, units::Length};
+
+/// 光栅的谐波次数上限
+const LENS_HARMONICS: u32 = 3;
+/// 网点的谐波次数上限
+const SCREEN_HARMONICS: u32 = 2;
+/// 周期小于该值的拍频表现为细纹理，不易察觉
+const FINE_PERIOD: Length = Length::mm(0.5);
+/// 周期大于该值的拍频表现为明显的条纹
+const BAND_PERIOD: Length = Length::mm(2.0);
+/// 光栅与图像节距在成品宽度上的累计错位超过该值（光栅数）时可见
+const LENS_DRIFT_VISIBLE: f64 = 0.1;
+/// 累计错位超过该值（光栅数）时画面在成品上明显翻转
+const LENS_DRIFT_FLIP: f64 = 0.5;
+/// 推荐光栅线数的搜索范围（相对值）与步长
+const LPI_SEARCH_RANGE: f64 = 0.05;
+const LPI_SEARCH_STEP: f64 = 0.001;
+/// 推荐值的最大数量
+const MAX_SUGGESTIONS: usize = 3;
+
+/// 龟纹分析参数
+#[derive(Debug, Clone, Default)]
+pub struct MoireOptions {
+    /// 光栅板的实际线数
+    pub lens_lpi: f64,
+    /// 印刷设备的物理分辨率
+    pub printer_dpi: Option<f64>,
+    /// 调幅网的网线频率，调频网不设置
+    pub screen_frequency: Option<f64>,
+    /// C/M/Y/K 的网角（度）
+    pub screen_angles: [f64; 4],
+}
+
+/// 风险等级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MoireRisk {
+    Low,
+    Medium,
+    High,
+}
+
+/// 一组可能产生龟纹的拍频
+#[derive(Debug, Clone)]
+pub struct MoireBeat {
+    /// 产生拍频的两个周期结构
+    pub source: String,
+    /// 拍频，单位：周期/英寸
+    pub frequency: f64,
+    /// 条纹周期，拍频为 0 时为 `None`
+    pub period: Option<Length>,
+    pub risk: MoireRisk,
+}
+
+/// 龟纹风险报告
+#[derive(Debug, Clone)]
+pub struct MoireReport {
+    /// 光栅合成图像的实际线数
+    pub image_lpi: f64,
+    pub beats: Vec<MoireBeat>,
+    /// 附近可降低网点龟纹风险的光栅线数，按与当前值的距离排序
+    pub suggested_lpi: Vec<f64>,
+    /// 与设备分辨率成整数倍的输出 DPI
+    pub suggested_dpi: Vec<f64>,
+}
+
+impl MoireReport {
+    /// 最高风险等级
+    pub fn max_risk(&self) -> MoireRisk {
+        self.beats
+            .iter()
+            .map(|beat| beat.risk)
+            .max()
+            .unwrap_or(MoireRisk::Low)
+    }
+}
+
+/// 按光栅线数、输出 DPI、设备分辨率与网点参数估算可能的龟纹
+///
+/// 只考虑水平方向的周期结构与低次谐波，结果用于在打样前排查明显风险。
+pub fn analyze_moire(output_info: &OutputInfo, options: &MoireOptions) -> MoireReport {
+    let lens_width: u32 = output_info.layout.lenticular_widths.iter().sum();
+    let output_dpi = output_info.dpi_w;
+    let image_lpi = if lens_width > 0 {
+        output_dpi / lens_width as f64
+    } else {
+        options.lens_lpi
+    };
+    let width = output_info.physical_width;
+    let beat = |source: String, frequency: f64| MoireBeat {
+        source,
+        frequency,
+        period: (frequency > 0.0).then(|| Length::inches(1.0 / frequency)),
+        risk: classify(frequency, width),
+    };
+
+    let lens_beat = (options.lens_lpi - image_lpi).abs();
+    let mut beats = vec![MoireBeat {
+        risk: classify_drift(lens_beat, width),
+        ..beat(
+            format!(
+                "光栅 {:.3} LPI / 图像节距 {:.3} LPI",
+                options.lens_lpi, image_lpi
+            ),
+            lens_beat,
+        )
+    }];
+
+    let mut suggested_dpi = vec![];
+    if let Some(printer_dpi) = options.printer_dpi {
+        let frequency = resample_beat(output_dpi, printer_dpi);
+        beats.push(beat(
+            format!(
+                "输出 {:.2} DPI / 设备 {:.0} DPI 重采样",
+                output_dpi, printer_dpi
+            ),
+            frequency,
+        ));
+        if classify(frequency, width) > MoireRisk::Low {
+            suggested_dpi = integer_dpis(output_dpi, printer_dpi);
+        }
+    }
+
+    let mut suggested_lpi = vec![];
+    if let Some(frequency) = options.screen_frequency {
+        let names = ["C", "M", "Y", "K"];
+        for (name, angle) in names.iter().zip(options.screen_angles) {
+            beats.push(beat(
+                format!(
+                    "{} 网 {:.0} LPI @ {}° / 光栅 {:.3} LPI",
+                    name, frequency, angle, options.lens_lpi
+                ),
+                screen_beat(frequency, angle, options.lens_lpi),
+            ));
+        }
+
+        let screen_risk = |lpi: f64| {
+            options
+                .screen_angles
+                .iter()
+                .map(|&angle| classify(screen_beat(frequency, angle, lpi), width))
+                .max()
+                .unwrap_or(MoireRisk::Low)
+        };
+        let current = screen_risk(options.lens_lpi);
+        if current > MoireRisk::Low {
+            let steps = (LPI_SEARCH_RANGE / LPI_SEARCH_STEP) as i32;
+            suggested_lpi = (1..=steps)
+                .flat_map(|step| [step, -step])
+                .map(|step| options.lens_lpi * (1.0 + step as f64 * LPI_SEARCH_STEP))
+                .filter(|&lpi| screen_risk(lpi) < current)
+                .take(MAX_SUGGESTIONS)
+                .collect();
+        }
+    }
+
+    MoireReport {
+        image_lpi,
+        beats,
+        suggested_lpi,
+        suggested_dpi,
+    }
+}
+
+/// 按拍频周期与成品宽度判断风险：周期超过成品宽度时看不到完整条纹
+fn classify(frequency: f64, width: Length) -> MoireRisk {
+    if frequency <= 0.0 {
+        return MoireRisk::Low;
+    }
+    let period = Length::inches(1.0 / frequency);
+    if period > width || period < FINE_PERIOD {
+        MoireRisk::Low
+    } else if period < BAND_PERIOD {
+        MoireRisk::Medium
+    } else {
+        MoireRisk::High
+    }
+}
+
+/// 按光栅与图像节距在成品宽度上的累计相位错位（光栅数）判断风险
+///
+/// 节距差产生的拍频周期往往超过成品宽度，但哪怕不足一个周期，
+/// 错位也会使成品两侧看到不同的帧。
+fn classify_drift(frequency: f64, width: Length) -> MoireRisk {
+    let drift = width.to_inches() * frequency;
+    if drift < LENS_DRIFT_VISIBLE {
+        MoireRisk::Low
+    } else if drift < LENS_DRIFT_FLIP {
+        MoireRisk::Medium
+    } else {
+        MoireRisk::High
+    }
+}
+
+/// 两种分辨率之间最近邻重采样产生的列宽交替频率，单位：周期/英寸
+fn resample_beat(output_dpi: f64, printer_dpi: f64) -> f64 {
+    let (high, low) = if printer_dpi >= output_dpi {
+        (printer_dpi, output_dpi)
+    } else {
+        (output_dpi, printer_dpi)
+    };
+    let ratio = high / low;
+    (ratio - ratio.round()).abs() * low
+}
+
+/// 网点两个方向、低次谐波与光栅各次谐波之间的最小拍频
+fn screen_beat(frequency: f64, angle: f64, lens_lpi: f64) -> f64 {
+    let mut min = f64::INFINITY;
+    for axis in [angle, angle + 90.0] {
+        let (sin, cos) = axis.to_radians().sin_cos();
+        for m in 1..=SCREEN_HARMONICS {
+            let (fx, fy) = (m as f64 * frequency * cos.abs(), m as f64 * frequency * sin);
+            for n in 1..=LENS_HARMONICS {
+                min = min.min((fx - n as f64 * lens_lpi).hypot(fy));
+            }
+        }
+    }
+    min
+}
+
+/// 与设备分辨率成整数倍关系、最接近当前输出 DPI 的值
+fn integer_dpis(output_dpi: f64, printer_dpi: f64) -> Vec<f64> {
+    let mut dpis = if printer_dpi >= output_dpi {
+        let ratio = printer_dpi / output_dpi;
+        [ratio.floor(), ratio.ceil()]
+            .into_iter()
+            .filter(|&k| k >= 1.0)
+            .map(|k| printer_dpi / k)
+            .collect::<Vec<_>>()
+    } else {
+        let ratio = output_dpi / printer_dpi;
+        [ratio.floor(), ratio.ceil()]
+            .into_iter()
+            .filter(|&k| k >= 1.0)
+            .map(|k| printer_dpi * k)
+            .collect::<Vec<_>>()
+    };
+    dpis.sort_by(|a, b| (a - output_dpi).abs().total_cmp(&(b - output_dpi).abs()));
+    dpis.dedup();
+    dpis
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lenticular::{FitMode, PageLayout, SourceParams};
+
+    use super::*;
+
+    #[test]
+    fn test_analyze_moire() {
+        let output_info = |dpi_w: f64| OutputInfo {
+            width: 1000,
+            height: 1000,
+            dpi_w,
+            dpi_h: dpi_w,
+            physical_width: Length::mm(100.0),
+            physical_height: Length::mm(100.0),
+            layout: PageLayout {
+                lenticular_widths: vec![2, 2],
+                ..Default::default()
+            },
+            icc_override: None,
+            cmyk_separation: None,
+            lzw: false,
+            fit_mode: FitMode::Stretch,
+            frame_sizes: vec![],
+            source_params: SourceParams::default(),
+        };
+
+        // 整数倍分辨率、调频网：无风险
+        let options = MoireOptions {
+            lens_lpi: 75.0,
+            printer_dpi: Some(2400.0),
+            ..Default::default()
+        };
+        let report = analyze_moire(&output_info(300.0), &options);
+        assert_eq!(report.max_risk(), MoireRisk::Low);
+        assert!(report.suggested_dpi.is_empty());
+
+        // 非整数倍：重采样产生条纹，推荐整数倍 DPI
+        let report = analyze_moire(&output_info(302.0), &options);
+        assert_eq!(report.max_risk(), MoireRisk::High);
+        assert_eq!(report.suggested_dpi[0], 300.0);
+
+        // 40 LPI、50mm：78 个光栅，图像节距 39.624 LPI，拍频周期超过成品宽度，
+        // 但成品两侧错位约 0.74 个光栅
+        let options = MoireOptions {
+            lens_lpi: 40.0,
+            ..Default::default()
+        };
+        let mut card = output_info(78.0 / Length::mm(50.0).to_inches() * 4.0);
+        card.physical_width = Length::mm(50.0);
+        let report = analyze_moire(&card, &options);
+        assert!(report.beats[0].period.unwrap() > card.physical_width);
+        assert_eq!(report.beats[0].risk, MoireRisk::High);
+
+        // 0° 网点的二次光栅谐波与网线频率接近
+        let options = MoireOptions {
+            lens_lpi: 75.0,
+            printer_dpi: None,
+            screen_frequency: Some(148.0),
+            screen_angles: [15.0, 75.0, 0.0, 45.0],
+        };
+        let report = analyze_moire(&output_info(300.0), &options);
+        assert_eq!(report.max_risk(), MoireRisk::High);
+        assert!(!report.suggested_lpi.is_empty());
+        // 推荐值使 0° 网点与光栅谐波锁相，条纹周期超过成品宽度
+        assert!(report
+            .suggested_lpi
+            .iter()
+            .all(
+                |&lpi| classify(screen_beat(148.0, 0.0, lpi), Length::mm(100.0)) == MoireRisk::Low
+            ));
+    }
+}
@@ -0,0 +1,122 @@
+use std::io::{BufRead, Seek, SeekFrom};
+
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder},
+    AnimationDecoder, DynamicImage, Frame, ImageFormat, ImageReader,
+};
+use log::debug;
+
+use crate::{error::Result, lenticular::SourceParams};
+
+use super::{dynamic::dynamic_to_frame_data, FrameData, MemoryFrameDecoder};
+
+/// 解码 GIF/APNG/WebP 动画的每一帧，按播放顺序返回
+///
+/// `target_count` 指定时按等间隔抽取到该帧数；动画帧数不足时保留全部帧。
+/// 非动画图像作为单帧返回。
+pub fn read_animation_frames<R>(
+    mut reader: R,
+    target_count: Option<usize>,
+) -> Result<Vec<MemoryFrameDecoder>>
+where
+    R: BufRead + Seek,
+{
+    reader.seek(SeekFrom::Start(0))?;
+    let format = ImageReader::new(&mut reader)
+        .with_guessed_format()?
+        .format();
+    reader.seek(SeekFrom::Start(0))?;
+
+    let frames: Vec<Frame> = match format {
+        Some(ImageFormat::Gif) => GifDecoder::new(reader)?.into_frames().collect_frames()?,
+        Some(ImageFormat::Png) => {
+            let decoder = PngDecoder::new(reader)?;
+            if !decoder.is_apng()? {
+                return Ok(vec![single_frame(DynamicImage::from_decoder(decoder)?)]);
+            }
+            decoder.apng()?.into_frames().collect_frames()?
+        }
+        Some(ImageFormat::WebP) => {
+            let decoder = WebPDecoder::new(reader)?;
+            if !decoder.has_animation() {
+                return Ok(vec![single_frame(DynamicImage::from_decoder(decoder)?)]);
+            }
+            decoder.into_frames().collect_frames()?
+        }
+        _ => {
+            let image = ImageReader::new(reader).with_guessed_format()?.decode()?;
+            return Ok(vec![single_frame(image)]);
+        }
+    };
+    debug!("Decoded {} animation frames", frames.len());
+
+    Ok(subsample_indices(frames.len(), target_count)
+        .into_iter()
+        .map(|index| single_frame(DynamicImage::ImageRgba8(frames[index].buffer().clone())))
+        .collect())
+}
+
+fn single_frame(image: DynamicImage) -> MemoryFrameDecoder {
+    let mut params = SourceParams::default();
+    params.set_source_dimensions(image.width(), image.height());
+
+    let data = dynamic_to_frame_data(image);
+    // 颜色类型与 `DynamicFrameDecoder` 的输出保持一致
+    params.set_color_type(match &data {
+        FrameData::U8(_) => tiff::ColorType::RGB(8),
+        FrameData::U16(_) => tiff::ColorType::RGB(16),
+    });
+    MemoryFrameDecoder::new(params, data)
+}
+
+/// 在 `count` 帧中等间隔选取 `target` 帧
+fn subsample_indices(count: usize, target: Option<usize>) -> Vec<usize> {
+    match target {
+        Some(target) if target > 0 && target < count => {
+            (0..target).map(|i| i * count / target).collect()
+        }
+        _ => (0..count).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{codecs::gif::GifEncoder, Rgba, RgbaImage};
+
+    use crate::decoder::FrameDecoder;
+
+    use super::*;
+
+    #[test]
+    fn test_subsample_indices() {
+        assert_eq!(subsample_indices(10, Some(4)), [0, 2, 5, 7]);
+        assert_eq!(subsample_indices(3, Some(5)), [0, 1, 2]);
+        assert_eq!(subsample_indices(3, None), [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_read_animation_frames_gif() {
+        let mut buf = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut buf);
+            for value in [0u8, 60, 120, 180, 240, 250] {
+                let image = RgbaImage::from_pixel(3, 2, Rgba([value, 0, 0, 255]));
+                encoder.encode_frame(Frame::new(image)).unwrap();
+            }
+        }
+
+        let mut frames = read_animation_frames(Cursor::new(buf), Some(3)).unwrap();
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames[0].params().color_type, Some(tiff::ColorType::RGB(8)));
+        let reds = frames
+            .iter_mut()
+            .map(|frame| match frame.read_image().unwrap() {
+                FrameData::U8(data) => data[0],
+                FrameData::U16(_) => panic!("expected 8-bit samples"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(reds, [0, 120, 240]);
+    }
+}
@@ -0,0 +1,119 @@
+use std::io::{BufRead, Seek, SeekFrom};
+
+use image::{DynamicImage, ImageDecoder, ImageReader};
+
+use crate::{error::Result, lenticular::SourceParams};
+
+use super::{FrameData, FrameDecoder};
+
+/// 基于 `image` 库的帧解码器，支持 PNG/JPEG/WebP/BMP 等常见格式
+///
+/// 输出统一为 RGB 8/16位，透明像素按白色底（纸白）合成。
+pub struct DynamicFrameDecoder<R> {
+    reader: R,
+}
+
+impl<R> DynamicFrameDecoder<R>
+where
+    R: BufRead + Seek,
+{
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    fn image_reader(&mut self) -> Result<ImageReader<&mut R>> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        Ok(ImageReader::new(&mut self.reader).with_guessed_format()?)
+    }
+}
+
+impl<R> FrameDecoder for DynamicFrameDecoder<R>
+where
+    R: BufRead + Seek,
+{
+    fn read_params(&mut self, _read_tags: bool) -> Result<SourceParams> {
+        let mut decoder = self.image_reader()?.into_decoder()?;
+
+        let mut params = SourceParams::default();
+        params.set_color_type(map_color_type(decoder.color_type()));
+        let (width, height) = decoder.dimensions();
+        params.set_source_dimensions(width, height);
+        params.set_icc_profile(decoder.icc_profile()?);
+
+        Ok(params)
+    }
+
+    fn read_image(&mut self) -> Result<FrameData> {
+        let image = self.image_reader()?.decode()?;
+        Ok(dynamic_to_frame_data(image))
+    }
+}
+
+/// 将 `image` 的颜色类型映射为解码输出的颜色类型
+fn map_color_type(color_type: image::ColorType) -> tiff::ColorType {
+    if color_type.bytes_per_pixel() / color_type.channel_count() > 1 {
+        tiff::ColorType::RGB(16)
+    } else {
+        tiff::ColorType::RGB(8)
+    }
+}
+
+pub(crate) fn dynamic_to_frame_data(image: DynamicImage) -> FrameData {
+    match map_color_type(image.color()) {
+        tiff::ColorType::RGB(16) => {
+            let rgba = image.into_rgba16();
+            let data = rgba
+                .pixels()
+                .flat_map(|px| {
+                    let [r, g, b, a] = px.0;
+                    [r, g, b].map(|v| over_white(v as u32, a as u32, u16::MAX as u32) as u16)
+                })
+                .collect();
+            FrameData::U16(data)
+        }
+        _ => {
+            let rgba = image.into_rgba8();
+            let data = rgba
+                .pixels()
+                .flat_map(|px| {
+                    let [r, g, b, a] = px.0;
+                    [r, g, b].map(|v| over_white(v as u32, a as u32, u8::MAX as u32) as u8)
+                })
+                .collect();
+            FrameData::U8(data)
+        }
+    }
+}
+
+/// 将带透明度的通道值合成到白色底上
+fn over_white(value: u32, alpha: u32, max: u32) -> u32 {
+    (value * alpha + max * (max - alpha) + max / 2) / max
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, Rgba, RgbaImage};
+
+    use super::*;
+
+    #[test]
+    fn test_dynamic_frame_decoder_png() {
+        let mut img = RgbaImage::from_pixel(4, 2, Rgba([10, 20, 30, 255]));
+        img.put_pixel(0, 0, Rgba([0, 0, 0, 0]));
+        let mut buf = Cursor::new(Vec::new());
+        img.write_to(&mut buf, ImageFormat::Png).unwrap();
+
+        let mut decoder = DynamicFrameDecoder::new(buf);
+        let params = decoder.read_params(true).unwrap();
+        assert_eq!(params.color_type, Some(tiff::ColorType::RGB(8)));
+        assert_eq!((params.width, params.height), (4, 2));
+
+        let FrameData::U8(data) = decoder.read_image().unwrap() else {
+            panic!("expected 8-bit samples");
+        };
+        assert_eq!(data.len(), 4 * 2 * 3);
+        assert_eq!(&data[..6], &[255, 255, 255, 10, 20, 30]);
+    }
+}
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use crate::{error::Result, lenticular::SourceParams};
+
+use super::{FrameData, FrameDecoder};
+
+/// 已解码到内存中的帧
+///
+/// 图像数据共享存储，克隆开销很小。
+#[derive(Debug, Clone)]
+pub struct MemoryFrameDecoder {
+    params: SourceParams,
+    data: Arc<FrameData>,
+}
+
+impl MemoryFrameDecoder {
+    pub fn new(params: SourceParams, data: FrameData) -> Self {
+        Self {
+            params,
+            data: Arc::new(data),
+        }
+    }
+
+    /// 完整解码另一个解码器的帧并缓存到内存中
+    pub fn from_decoder<D>(decoder: &mut D) -> Result<Self>
+    where
+        D: FrameDecoder + ?Sized,
+    {
+        let params = decoder.read_params(true)?;
+        let data = decoder.read_image()?;
+        Ok(Self::new(params, data))
+    }
+
+    pub fn params(&self) -> &SourceParams {
+        &self.params
+    }
+}
+
+impl FrameDecoder for MemoryFrameDecoder {
+    fn read_params(&mut self, _read_tags: bool) -> Result<SourceParams> {
+        Ok(self.params.clone())
+    }
+
+    fn read_image(&mut self) -> Result<FrameData> {
+        Ok((*self.data).clone())
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(feature = "image")]
+mod animation;
+#[cfg(feature = "image")]
+mod dynamic;
+mod memory;
+mod tiff;
+
+#[cfg(feature = "image")]
+pub use animation::read_animation_frames;
+#[cfg(feature = "image")]
+pub use dynamic::DynamicFrameDecoder;
+pub use memory::MemoryFrameDecoder;
+pub use tiff::{read_tiff_pages, SharedTiffReader, TiffFrameDecoder};
+
+use crate::{error::Result, lenticular::SourceParams};
+
+/// 帧解码器，为光栅合成提供单帧图像
+///
+/// 同一解码器的方法可能被多次调用，实现需保证每次调用都从头读取。
+pub trait FrameDecoder {
+    /// 读取图像元数据参数
+    ///
+    /// `read_tags` 为 true 时同时读取分辨率等附加信息
+    fn read_params(&mut self, read_tags: bool) -> Result<SourceParams>;
+
+    /// 读取图像数据，采样按行优先、通道交错排列
+    fn read_image(&mut self) -> Result<FrameData>;
+}
+
+impl<D> FrameDecoder for Box<D>
+where
+    D: FrameDecoder + ?Sized,
+{
+    fn read_params(&mut self, read_tags: bool) -> Result<SourceParams> {
+        (**self).read_params(read_tags)
+    }
+
+    fn read_image(&mut self) -> Result<FrameData> {
+        (**self).read_image()
+    }
+}
+
+/// 解码后的图像数据
+#[derive(Debug, Clone)]
+pub enum FrameData {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+}
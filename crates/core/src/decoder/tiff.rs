@@ -0,0 +1,199 @@
+use std::{
+    io::{Cursor, Read, Seek, SeekFrom},
+    sync::Arc,
+};
+
+use log::debug;
+
+use tiff::{decoder::DecodingResult as TiffDecodingResult, tags::Tag as TiffTag};
+
+use crate::{
+    error::{Error, Result},
+    lenticular::{tiff::ICC_PROFILE_TAG, SourceParams},
+};
+
+use super::{FrameData, FrameDecoder};
+
+/// 多页 TIFF 各页共享的文件内容
+pub type SharedTiffReader = Cursor<Arc<[u8]>>;
+
+/// TIFF 帧解码器
+///
+/// 默认读取第一个 IFD，可通过 [`TiffFrameDecoder::with_page`] 指定页。
+pub struct TiffFrameDecoder<R> {
+    reader: R,
+    page: usize,
+}
+
+impl<R> TiffFrameDecoder<R>
+where
+    R: Read + Seek,
+{
+    pub fn new(reader: R) -> Self {
+        Self { reader, page: 0 }
+    }
+
+    /// 读取指定页（IFD 索引，从 0 开始）
+    pub fn with_page(reader: R, page: usize) -> Self {
+        Self { reader, page }
+    }
+
+    pub fn page(&self) -> usize {
+        self.page
+    }
+
+    fn decoder(&mut self) -> Result<tiff::decoder::Decoder<&mut R>> {
+        self.reader.seek(SeekFrom::Start(0))?;
+        let mut decoder = tiff::decoder::Decoder::new(&mut self.reader)?;
+        if self.page != 0 {
+            decoder.seek_to_image(self.page)?;
+        }
+        Ok(decoder)
+    }
+}
+
+/// 将多页 TIFF 的每一页拆分为单独的帧，按页序返回
+///
+/// 标记为缩略图（NewSubfileType 第 0 位）的页会被跳过。
+pub fn read_tiff_pages<R>(mut reader: R) -> Result<Vec<TiffFrameDecoder<SharedTiffReader>>>
+where
+    R: Read,
+{
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    let data: Arc<[u8]> = buf.into();
+
+    let mut decoder = tiff::decoder::Decoder::new(Cursor::new(data.clone()))?;
+    let mut pages = vec![];
+    let mut page = 0;
+    loop {
+        let subfile_type = decoder
+            .find_tag_unsigned::<u32>(TiffTag::NewSubfileType)?
+            .unwrap_or(0);
+        if subfile_type & 1 == 0 {
+            pages.push(TiffFrameDecoder::with_page(Cursor::new(data.clone()), page));
+        } else {
+            debug!("Skipping reduced resolution page {}", page);
+        }
+
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+        page += 1;
+    }
+
+    Ok(pages)
+}
+
+impl<R> FrameDecoder for TiffFrameDecoder<R>
+where
+    R: Read + Seek,
+{
+    fn read_params(&mut self, read_tags: bool) -> Result<SourceParams> {
+        let mut decoder = self.decoder()?;
+        read_params_from_tiff(&mut decoder, read_tags)
+    }
+
+    fn read_image(&mut self) -> Result<FrameData> {
+        let mut decoder = self.decoder()?;
+        match decoder.read_image()? {
+            TiffDecodingResult::U8(data) => Ok(FrameData::U8(data)),
+            TiffDecodingResult::U16(data) => Ok(FrameData::U16(data)),
+            _ => Err(Error::InvalidInput(
+                "图像数据读取失败: 非预期的编码类型，仅接受 8/16位整数采样".to_string(),
+            )),
+        }
+    }
+}
+
+/// 从解码器中读取图片元数据参数
+fn read_params_from_tiff<R>(
+    decoder: &mut tiff::decoder::Decoder<R>,
+    read_tags: bool,
+) -> Result<SourceParams>
+where
+    R: Read + Seek,
+{
+    let mut params = SourceParams::default();
+
+    params.set_color_type(decoder.colortype()?);
+    let (width, height) = decoder.dimensions()?;
+    params.set_source_dimensions(width, height);
+    let icc_profile = decoder
+        .find_tag(ICC_PROFILE_TAG)?
+        .map(|v| v.into_u8_vec())
+        .transpose()?;
+    params.set_icc_profile(icc_profile);
+
+    // 分辨率标签可以缺失，此时 `SourceParams::dpi` 返回 `None`
+    if read_tags {
+        let x_resolution = decoder.find_tag(TiffTag::XResolution)?;
+        let y_resolution = decoder.find_tag(TiffTag::YResolution)?;
+        if let (Some(x_resolution), Some(y_resolution)) = (x_resolution, y_resolution) {
+            // ResolutionUnit 缺省为 2（英寸）
+            let resolution_unit = decoder
+                .find_tag(TiffTag::ResolutionUnit)?
+                .map(|v| v.into_u32())
+                .transpose()?
+                .unwrap_or(2);
+            params.set_resolution(resolution_unit, x_resolution, y_resolution);
+        }
+    }
+
+    Ok(params)
+}
+
+#[cfg(test)]
+mod tests {
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    use super::*;
+    use crate::lenticular::test_util::tiff_with_resolution as cmyk8_tiff;
+
+    #[test]
+    fn test_read_tiff_pages() {
+        let mut buf = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut buf).unwrap();
+        for (value, subfile_type) in [(10u8, 0u32), (20, 1), (30, 0), (40, 0)] {
+            let mut image = encoder.new_image::<colortype::CMYK8>(2, 2).unwrap();
+            image
+                .encoder()
+                .write_tag(TiffTag::NewSubfileType, subfile_type)
+                .unwrap();
+            image.write_data(&[value; 2 * 2 * 4]).unwrap();
+        }
+
+        let mut pages = read_tiff_pages(Cursor::new(buf.into_inner())).unwrap();
+        assert_eq!(
+            pages.iter().map(|p| p.page()).collect::<Vec<_>>(),
+            [0, 2, 3]
+        );
+
+        let values = pages
+            .iter_mut()
+            .map(|p| match p.read_image().unwrap() {
+                FrameData::U8(data) => data[0],
+                FrameData::U16(_) => panic!("expected 8-bit samples"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(values, [10, 30, 40]);
+    }
+
+    #[test]
+    fn test_optional_resolution_tags() {
+        // 没有分辨率标签时不报错，分辨率为 None
+        let params = cmyk8_tiff(&[]).read_params(true).unwrap();
+        assert_eq!((params.width, params.height), (2, 2));
+        assert_eq!(params.dpi(), None);
+
+        // 缺少 ResolutionUnit 时按英寸处理
+        let resolution = [(TiffTag::XResolution, 300), (TiffTag::YResolution, 150)];
+        let params = cmyk8_tiff(&resolution).read_params(true).unwrap();
+        assert_eq!(params.dpi(), Some((300.0, 150.0)));
+        let params = cmyk8_tiff(&[resolution[0], resolution[1], (TiffTag::ResolutionUnit, 3)])
+            .read_params(true)
+            .unwrap();
+        assert_eq!(params.dpi(), Some((300.0 * 2.54, 150.0 * 2.54)));
+    }
+}
@@ -16,4 +16,7 @@ pub enum Error {
     DifferentTypesOfPixels(#[from] fast_image_resize::DifferentTypesOfPixelsError),
     #[error("Tiff error: {0}")]
     Tiff(#[from] tiff::TiffError),
+    #[cfg(feature = "image")]
+    #[error("Image error: {0}")]
+    Image(#[from] image::ImageError),
 }
@@ -0,0 +1,110 @@
+use super::{Cmyk16Color, Cmyk8Color, MatrixImage, Rgb16Color, Rgb8Color};
+
+/// CMYK 16位转 8位，`dither` 为 true 时使用 Floyd-Steinberg 误差扩散
+pub fn cmyk16_to_cmyk8(img: &MatrixImage<Cmyk16Color>, dither: bool) -> MatrixImage<Cmyk8Color> {
+    let samples = img.inner().iter().map(|c| [c.c, c.m, c.y, c.k]);
+    let quantized = quantize_to_8bit(samples, img.width() as usize, dither);
+
+    let mut out = MatrixImage::new(img.width(), img.height());
+    out.inner_mut()
+        .iter_mut()
+        .zip(quantized)
+        .for_each(|(dst, [c, m, y, k])| *dst = Cmyk8Color { c, m, y, k });
+    if let Some(info) = img.info() {
+        out.set_info(info.clone());
+    }
+    out.set_icc_profile(img.icc_profile().map(|p| p.to_vec()));
+    out.set_layout(img.layout().cloned());
+    out.set_lzw(img.lzw());
+    out
+}
+
+/// RGB 16位转 8位，`dither` 为 true 时使用 Floyd-Steinberg 误差扩散
+pub fn rgb16_to_rgb8(img: &MatrixImage<Rgb16Color>, dither: bool) -> MatrixImage<Rgb8Color> {
+    let samples = img.inner().iter().map(|c| [c.r, c.g, c.b]);
+    let quantized = quantize_to_8bit(samples, img.width() as usize, dither);
+
+    let mut out = MatrixImage::new(img.width(), img.height());
+    out.inner_mut()
+        .iter_mut()
+        .zip(quantized)
+        .for_each(|(dst, [r, g, b])| *dst = Rgb8Color { r, g, b });
+    if let Some(info) = img.info() {
+        out.set_info(info.clone());
+    }
+    out.set_icc_profile(img.icc_profile().map(|p| p.to_vec()));
+    out.set_layout(img.layout().cloned());
+    out.set_lzw(img.lzw());
+    out
+}
+
+/// 按行优先顺序量化像素采样
+fn quantize_to_8bit<const N: usize>(
+    samples: impl Iterator<Item = [u16; N]>,
+    width: usize,
+    dither: bool,
+) -> Vec<[u8; N]> {
+    if !dither {
+        return samples
+            .map(|px| px.map(|v| ((v as u32 * 255 + 32767) / 65535) as u8))
+            .collect();
+    }
+
+    // 当前行与下一行的累计误差，以 8 位量级表示；两端各留一个像素的余量
+    let mut err_cur = vec![[0f32; N]; width + 2];
+    let mut err_next = vec![[0f32; N]; width + 2];
+    let mut out = Vec::new();
+
+    for (i, px) in samples.enumerate() {
+        let x = i % width;
+        if x == 0 && i != 0 {
+            std::mem::swap(&mut err_cur, &mut err_next);
+            err_next.iter_mut().for_each(|e| *e = [0.0; N]);
+        }
+
+        let mut q = [0u8; N];
+        for ch in 0..N {
+            let value = px[ch] as f32 * 255.0 / 65535.0 + err_cur[x + 1][ch];
+            let rounded = value.round().clamp(0.0, 255.0);
+            q[ch] = rounded as u8;
+
+            let err = value - rounded;
+            err_cur[x + 2][ch] += err * 7.0 / 16.0;
+            err_next[x][ch] += err * 3.0 / 16.0;
+            err_next[x + 1][ch] += err * 5.0 / 16.0;
+            err_next[x + 2][ch] += err / 16.0;
+        }
+        out.push(q);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cmyk16_to_cmyk8_dither_preserves_mean() {
+        // 介于两个 8 位色阶之间的平涂色块
+        let value = 257 * 100 + 128;
+        let mut img: MatrixImage<Cmyk16Color> = MatrixImage::new(64, 64);
+        img.inner_mut().iter_mut().for_each(|c| {
+            *c = Cmyk16Color {
+                c: value,
+                m: 0,
+                y: u16::MAX,
+                k: value,
+            }
+        });
+
+        let plain = cmyk16_to_cmyk8(&img, false);
+        assert!(plain.inner().iter().all(|c| c.c == 100 && c.y == 255));
+
+        let dithered = cmyk16_to_cmyk8(&img, true);
+        let mean = dithered.inner().iter().map(|c| c.c as f64).sum::<f64>() / (64.0 * 64.0);
+        assert!((mean - value as f64 / 257.0).abs() < 0.05, "mean: {mean}");
+        assert!(dithered.inner().iter().any(|c| c.c == 101));
+        assert!(dithered.inner().iter().all(|c| c.m == 0 && c.y == 255));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::units::Length;
+
+/// 裁切标记与出血之间的间距
+const CROP_MARK_OFFSET: Length = Length::mm(1.0);
+/// 裁切标记长度
+const CROP_MARK_LENGTH: Length = Length::mm(5.0);
+/// 裁切标记线宽
+const CROP_MARK_THICKNESS: Length = Length::mm(0.1);
+/// 对位条与出血之间的间距
+const REGISTRATION_BAR_OFFSET: Length = Length::mm(1.0);
+/// 对位条高度（光栅线与帧色条各占一半）
+const REGISTRATION_BAR_HEIGHT: Length = Length::mm(4.0);
+
+/// 印后加工版面，单位均为像素
+///
+/// 光栅合成区域为成品尺寸加上四周出血，裁切标记画在出血以外的空白区域。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageLayout {
+    /// 成品宽度
+    pub trim_width: u32,
+    /// 成品高度
+    pub trim_height: u32,
+    /// 左右出血，为整数个光栅宽度
+    pub bleed_x: u32,
+    /// 上下出血
+    pub bleed_y: u32,
+    /// 安全边距，设置时绘制参考线（仅用于打样检查）
+    pub safe_margin: Option<u32>,
+    /// 裁切标记
+    pub crop_marks: Option<CropMarks>,
+    /// 光栅对位条
+    pub registration_bars: Option<RegistrationBars>,
+    /// 各帧的光栅宽度表，按帧在光栅内从左到右的位置排列，与光栅合成使用的列映射一致
+    pub lenticular_widths: Vec<u32>,
+    /// 背面印刷：图像已镜像，光栅内的帧顺序与输入相反
+    pub reverse_print: bool,
+}
+
+/// 裁切标记尺寸，单位：像素
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropMarks {
+    /// 与出血边缘的间距
+    pub offset: u32,
+    pub length: u32,
+    pub thickness: u32,
+}
+
+impl CropMarks {
+    /// 按输出 DPI 计算标准尺寸的裁切标记
+    pub fn with_dpi(dpi: f64) -> Self {
+        let px = |length: Length| (length.to_px(dpi).round() as u32).max(1);
+        Self {
+            offset: px(CROP_MARK_OFFSET),
+            length: px(CROP_MARK_LENGTH),
+            thickness: px(CROP_MARK_THICKNESS),
+        }
+    }
+}
+
+/// 光栅对位条尺寸，单位：像素
+///
+/// 对位条画在成品上下两侧的标记区域：外侧为每个光栅起始列的套准黑细线，
+/// 内侧为按帧序号着色的色条，供印刷时将光栅板与画面对齐。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegistrationBars {
+    /// 与出血边缘的间距
+    pub offset: u32,
+    pub height: u32,
+}
+
+impl RegistrationBars {
+    /// 按输出 DPI 计算标准尺寸的对位条
+    pub fn with_dpi(dpi: f64) -> Self {
+        let px = |length: Length| (length.to_px(dpi).round() as u32).max(1);
+        Self {
+            offset: px(REGISTRATION_BAR_OFFSET),
+            height: px(REGISTRATION_BAR_HEIGHT).max(2),
+        }
+    }
+}
+
+impl PageLayout {
+    /// 光栅合成区域宽度（含出血）
+    pub fn bleed_width(&self) -> u32 {
+        self.trim_width + self.bleed_x * 2
+    }
+
+    /// 光栅合成区域高度（含出血）
+    pub fn bleed_height(&self) -> u32 {
+        self.trim_height + self.bleed_y * 2
+    }
+
+    /// 出血以外标记区域的宽度
+    pub fn slug(&self) -> u32 {
+        let crop_marks = self
+            .crop_marks
+            .map(|marks| marks.offset + marks.length)
+            .unwrap_or(0);
+        let registration_bars = self
+            .registration_bars
+            .map(|bars| bars.offset + bars.height)
+            .unwrap_or(0);
+        crop_marks.max(registration_bars)
+    }
+
+    /// 是否需要在写出时绘制标记
+    pub fn has_marks(&self) -> bool {
+        self.safe_margin.is_some() || self.crop_marks.is_some() || self.registration_bars.is_some()
+    }
+}
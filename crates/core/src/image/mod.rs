@@ -2,9 +2,17 @@ use ndarray::{Array, Array2, Order};
 
 use crate::error::Result;
 
+mod convert;
+mod layout;
+mod proof;
 mod resize;
+mod separation;
 
-pub use resize::resize_cmyk8;
+pub use convert::{cmyk16_to_cmyk8, rgb16_to_rgb8};
+pub use layout::{CropMarks, PageLayout, RegistrationBars};
+pub use proof::{shrink_to_width, soft_proof, ProofConversion, ProofOptions};
+pub use resize::{resize_cmyk16, resize_cmyk8, resize_gray8, resize_rgb16, resize_rgb8};
+pub use separation::{rgb8_to_cmyk8, BlackGeneration, CmykSeparation};
 
 pub trait Color: Sized + Clone + Default {
     fn from_slice(slice: &[u8]) -> Vec<Self>;
@@ -33,6 +41,86 @@ impl Color for Cmyk8Color {
     }
 }
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Cmyk16Color {
+    pub c: u16,
+    pub m: u16,
+    pub y: u16,
+    pub k: u16,
+}
+
+impl Color for Cmyk16Color {
+    /// 输入为本机字节序的 16 位采样
+    fn from_slice(slice: &[u8]) -> Vec<Self> {
+        slice
+            .chunks(8)
+            .map(|chunk| Cmyk16Color {
+                c: u16::from_ne_bytes([chunk[0], chunk[1]]),
+                m: u16::from_ne_bytes([chunk[2], chunk[3]]),
+                y: u16::from_ne_bytes([chunk[4], chunk[5]]),
+                k: u16::from_ne_bytes([chunk[6], chunk[7]]),
+            })
+            .collect()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rgb8Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color for Rgb8Color {
+    fn from_slice(slice: &[u8]) -> Vec<Self> {
+        slice
+            .chunks(3)
+            .map(|chunk| Rgb8Color {
+                r: chunk[0],
+                g: chunk[1],
+                b: chunk[2],
+            })
+            .collect()
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rgb16Color {
+    pub r: u16,
+    pub g: u16,
+    pub b: u16,
+}
+
+impl Color for Rgb16Color {
+    /// 输入为本机字节序的 16 位采样
+    fn from_slice(slice: &[u8]) -> Vec<Self> {
+        slice
+            .chunks(6)
+            .map(|chunk| Rgb16Color {
+                r: u16::from_ne_bytes([chunk[0], chunk[1]]),
+                g: u16::from_ne_bytes([chunk[2], chunk[3]]),
+                b: u16::from_ne_bytes([chunk[4], chunk[5]]),
+            })
+            .collect()
+    }
+}
+
+/// 单通道 8位，用于白墨等专色通道
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Gray8Color {
+    pub v: u8,
+}
+
+impl Color for Gray8Color {
+    fn from_slice(slice: &[u8]) -> Vec<Self> {
+        slice.iter().map(|&v| Gray8Color { v }).collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DpiInfo {
     pub dpi_h: f64,
@@ -49,6 +137,9 @@ pub struct ImageInfo {
 pub struct MatrixImage<C> {
     mat: Array2<C>,
     info: Option<DpiInfo>,
+    icc_profile: Option<Vec<u8>>,
+    layout: Option<PageLayout>,
+    lzw: bool,
 }
 
 impl<C> MatrixImage<C>
@@ -62,14 +153,26 @@ where
             .to_shape((shape, Order::RowMajor))?
             .to_owned();
 
-        Ok(MatrixImage { mat, info: None })
+        Ok(MatrixImage {
+            mat,
+            info: None,
+            icc_profile: None,
+            layout: None,
+            lzw: false,
+        })
     }
 
     pub fn new(width: u32, height: u32) -> Self {
         let shape = (height as usize, width as usize);
         let mat = Array::default(shape);
 
-        MatrixImage { mat, info: None }
+        MatrixImage {
+            mat,
+            info: None,
+            icc_profile: None,
+            layout: None,
+            lzw: false,
+        }
     }
 
     pub fn inner(&self) -> &Array2<C> {
@@ -103,6 +206,32 @@ where
     pub fn info(&self) -> Option<&DpiInfo> {
         self.info.as_ref()
     }
+
+    pub fn set_icc_profile(&mut self, icc_profile: Option<Vec<u8>>) {
+        self.icc_profile = icc_profile
+    }
+
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.icc_profile.as_deref()
+    }
+
+    /// 印后加工版面，CMYK 8位图像写出时据此绘制标记
+    pub fn set_layout(&mut self, layout: Option<PageLayout>) {
+        self.layout = layout
+    }
+
+    pub fn layout(&self) -> Option<&PageLayout> {
+        self.layout.as_ref()
+    }
+
+    /// 写出 TIFF 时使用 LZW 压缩
+    pub fn set_lzw(&mut self, lzw: bool) {
+        self.lzw = lzw
+    }
+
+    pub fn lzw(&self) -> bool {
+        self.lzw
+    }
 }
 
 impl MatrixImage<Cmyk8Color> {
@@ -114,6 +243,39 @@ impl MatrixImage<Cmyk8Color> {
     }
 }
 
+impl MatrixImage<Cmyk16Color> {
+    pub fn to_samples(&self) -> Vec<u16> {
+        self.mat
+            .iter()
+            .flat_map(|c| [c.c, c.m, c.y, c.k])
+            .collect::<Vec<u16>>()
+    }
+}
+
+impl MatrixImage<Rgb8Color> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.mat
+            .iter()
+            .flat_map(|c| [c.r, c.g, c.b])
+            .collect::<Vec<u8>>()
+    }
+}
+
+impl MatrixImage<Rgb16Color> {
+    pub fn to_samples(&self) -> Vec<u16> {
+        self.mat
+            .iter()
+            .flat_map(|c| [c.r, c.g, c.b])
+            .collect::<Vec<u16>>()
+    }
+}
+
+impl MatrixImage<Gray8Color> {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.mat.iter().map(|c| c.v).collect::<Vec<u8>>()
+    }
+}
+
 pub struct TypedRawImage<C> {
     data: Vec<u8>,
     info: ImageInfo,
@@ -133,4 +295,16 @@ where
             _color_marker: std::marker::PhantomData,
         }
     }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn image_info(&self) -> &ImageInfo {
+        &self.info
+    }
+
+    pub fn dpi_info(&self) -> &DpiInfo {
+        &self.dpi_info
+    }
 }
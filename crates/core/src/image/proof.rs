@@ -0,0 +1,154 @@
+use crate::error::Result;
+
+use super::{resize_rgb8, Cmyk8Color, MatrixImage, Rgb8Color};
+
+/// CMYK 到 sRGB 的预览转换方式
+///
+/// 两种方式均不依赖 ICC 配置文件，仅用于屏幕上的快速检查。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProofConversion {
+    /// 简单补色公式：R = (1-C)(1-K)
+    Naive,
+    /// 按典型印刷油墨颜色在线性光下逐层叠加，更接近印刷品的色域与暗部
+    #[default]
+    InkModel,
+}
+
+/// 软打样预览选项
+#[derive(Debug, Clone, Default)]
+pub struct ProofOptions {
+    pub conversion: ProofConversion,
+    /// 预览图最大宽度，超过时按比例缩小
+    pub max_width: Option<u32>,
+}
+
+/// 典型印刷油墨（C、M、Y、K）满版时的 sRGB 颜色
+const INK_COLORS: [[u8; 3]; 4] = [[0, 158, 224], [236, 0, 140], [255, 241, 0], [35, 31, 32]];
+
+/// 将 CMYK 8位图像转换为 sRGB 预览图
+pub fn soft_proof(
+    img: &MatrixImage<Cmyk8Color>,
+    options: &ProofOptions,
+) -> Result<MatrixImage<Rgb8Color>> {
+    let ink_reflectance = INK_COLORS.map(|rgb| rgb.map(srgb_to_linear));
+
+    let mut out = MatrixImage::new(img.width(), img.height());
+    out.inner_mut()
+        .iter_mut()
+        .zip(img.inner().iter())
+        .for_each(|(dst, px)| {
+            *dst = match options.conversion {
+                ProofConversion::Naive => naive_to_rgb(px),
+                ProofConversion::InkModel => ink_model_to_rgb(px, &ink_reflectance),
+            }
+        });
+
+    shrink_to_width(out, options.max_width)
+}
+
+/// 按最大宽度等比缩小预览图，`max_width` 为 `None` 或不小于原宽度时保持原图
+pub fn shrink_to_width(
+    img: MatrixImage<Rgb8Color>,
+    max_width: Option<u32>,
+) -> Result<MatrixImage<Rgb8Color>> {
+    match max_width {
+        Some(max_width) if max_width > 0 && max_width < img.width() => {
+            let height = ((img.height() as f64 * max_width as f64 / img.width() as f64).round()
+                as u32)
+                .max(1);
+            let resized = resize_rgb8(
+                img.to_bytes(),
+                img.width(),
+                img.height(),
+                max_width,
+                height,
+                fast_image_resize::ResizeAlg::Convolution(fast_image_resize::FilterType::Bilinear),
+            )?;
+            MatrixImage::from_slice(&resized, max_width, height)
+        }
+        _ => Ok(img),
+    }
+}
+
+fn naive_to_rgb(px: &Cmyk8Color) -> Rgb8Color {
+    let white = 255 - px.k as u32;
+    let channel = |v: u8| ((255 - v as u32) * white / 255) as u8;
+    Rgb8Color {
+        r: channel(px.c),
+        g: channel(px.m),
+        b: channel(px.y),
+    }
+}
+
+fn ink_model_to_rgb(px: &Cmyk8Color, ink_reflectance: &[[f64; 3]; 4]) -> Rgb8Color {
+    let coverage = [px.c, px.m, px.y, px.k].map(|v| v as f64 / 255.0);
+    let mut linear = [1.0f64; 3];
+    for (ink, amount) in ink_reflectance.iter().zip(coverage) {
+        for ch in 0..3 {
+            linear[ch] *= 1.0 - amount * (1.0 - ink[ch]);
+        }
+    }
+    let [r, g, b] = linear.map(linear_to_srgb);
+    Rgb8Color { r, g, b }
+}
+
+fn srgb_to_linear(v: u8) -> f64 {
+    let v = v as f64 / 255.0;
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f64) -> u8 {
+    let v = v.clamp(0.0, 1.0);
+    let v = if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (v * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_soft_proof() {
+        let mut img: MatrixImage<Cmyk8Color> = MatrixImage::new(40, 20);
+        img.inner_mut()[[0, 1]] = Cmyk8Color {
+            c: 255,
+            m: 0,
+            y: 0,
+            k: 0,
+        };
+
+        for conversion in [ProofConversion::Naive, ProofConversion::InkModel] {
+            let options = ProofOptions {
+                conversion,
+                max_width: None,
+            };
+            let out = soft_proof(&img, &options).unwrap();
+            // 纸白保持白色，青色以蓝绿为主
+            assert_eq!(
+                out.inner()[[0, 0]],
+                Rgb8Color {
+                    r: 255,
+                    g: 255,
+                    b: 255
+                }
+            );
+            let cyan = out.inner()[[0, 1]];
+            assert!(cyan.r < 10 && cyan.b > 200);
+        }
+
+        let options = ProofOptions {
+            max_width: Some(10),
+            ..Default::default()
+        };
+        let out = soft_proof(&img, &options).unwrap();
+        assert_eq!((out.width(), out.height()), (10, 5));
+    }
+}
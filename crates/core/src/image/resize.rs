@@ -11,23 +11,121 @@ pub fn resize_cmyk8(
     out_width: u32,
     out_height: u32,
     alg: ResizeAlg,
+) -> Result<Vec<u8>> {
+    resize_raw(
+        src,
+        width,
+        height,
+        out_width,
+        out_height,
+        PixelType::U8x4,
+        alg,
+    )
+}
+
+/// 缩放 CMYK 16位图像，输入输出均为本机字节序的原始字节
+pub fn resize_cmyk16(
+    src: Vec<u8>,
+    width: u32,
+    height: u32,
+    out_width: u32,
+    out_height: u32,
+    alg: ResizeAlg,
+) -> Result<Vec<u8>> {
+    resize_raw(
+        src,
+        width,
+        height,
+        out_width,
+        out_height,
+        PixelType::U16x4,
+        alg,
+    )
+}
+
+pub fn resize_rgb8(
+    src: Vec<u8>,
+    width: u32,
+    height: u32,
+    out_width: u32,
+    out_height: u32,
+    alg: ResizeAlg,
+) -> Result<Vec<u8>> {
+    resize_raw(
+        src,
+        width,
+        height,
+        out_width,
+        out_height,
+        PixelType::U8x3,
+        alg,
+    )
+}
+
+/// 缩放 RGB 16位图像，输入输出均为本机字节序的原始字节
+pub fn resize_rgb16(
+    src: Vec<u8>,
+    width: u32,
+    height: u32,
+    out_width: u32,
+    out_height: u32,
+    alg: ResizeAlg,
+) -> Result<Vec<u8>> {
+    resize_raw(
+        src,
+        width,
+        height,
+        out_width,
+        out_height,
+        PixelType::U16x3,
+        alg,
+    )
+}
+
+pub fn resize_gray8(
+    src: Vec<u8>,
+    width: u32,
+    height: u32,
+    out_width: u32,
+    out_height: u32,
+    alg: ResizeAlg,
+) -> Result<Vec<u8>> {
+    resize_raw(
+        src,
+        width,
+        height,
+        out_width,
+        out_height,
+        PixelType::U8,
+        alg,
+    )
+}
+
+fn resize_raw(
+    src: Vec<u8>,
+    width: u32,
+    height: u32,
+    out_width: u32,
+    out_height: u32,
+    pixel_type: PixelType,
+    alg: ResizeAlg,
 ) -> Result<Vec<u8>> {
     let input_height =
         NonZero::new(height).ok_or(Error::InvalidInput("height cannot be zero".to_string()))?;
     let input_width =
-        NonZero::new(width).ok_or(Error::InvalidInput("height cannot be zero".to_string()))?;
+        NonZero::new(width).ok_or(Error::InvalidInput("width cannot be zero".to_string()))?;
     let output_height =
         NonZero::new(out_height).ok_or(Error::InvalidInput("height cannot be zero".to_string()))?;
     let output_width =
-        NonZero::new(out_width).ok_or(Error::InvalidInput("height cannot be zero".to_string()))?;
+        NonZero::new(out_width).ok_or(Error::InvalidInput("width cannot be zero".to_string()))?;
 
-    let src_image = Image::from_vec_u8(input_width, input_height, src, PixelType::U8x4)?;
+    let src_image = Image::from_vec_u8(input_width, input_height, src, pixel_type)?;
 
-    let mut dst_image = Image::new(output_width, output_height, PixelType::U8x4);
+    let mut dst_image = Image::new(output_width, output_height, pixel_type);
     let mut dst_view = dst_image.view_mut();
 
     let mut resizer = Resizer::new(alg);
     resizer.resize(&src_image.view(), &mut dst_view)?;
 
-    Ok(dst_image.buffer().to_vec())
+    Ok(dst_image.into_vec())
 }
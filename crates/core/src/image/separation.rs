@@ -0,0 +1,174 @@
+use super::{Cmyk8Color, MatrixImage, Rgb16Color, Rgb8Color};
+
+/// 黑版生成方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlackGeneration {
+    /// 不生成黑版，仅使用 CMY 三色
+    None,
+    /// 底色去除（UCR）：仅在暗部（灰成分超过 50%）以黑替代 CMY 的灰成分，
+    /// `amount` 为最大替代比例（0~1）
+    Ucr { amount: f64 },
+    /// 灰成分替代（GCR）：在所有色调中按 `amount` 比例（0~1）以黑替代 CMY 的灰成分
+    Gcr { amount: f64 },
+}
+
+/// RGB 到 CMYK 的分色参数
+///
+/// 使用不依赖 ICC 配置文件的简单公式分色，适合没有色彩管理条件时的快速处理。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CmykSeparation {
+    pub black_generation: BlackGeneration,
+    /// 总墨量上限，单位：百分比（0~400）
+    pub total_ink_limit: f64,
+}
+
+impl Default for CmykSeparation {
+    fn default() -> Self {
+        Self {
+            black_generation: BlackGeneration::Gcr { amount: 0.7 },
+            total_ink_limit: 300.0,
+        }
+    }
+}
+
+impl CmykSeparation {
+    /// 分色单个像素，输入输出均为 0~1
+    pub fn separate(&self, r: f64, g: f64, b: f64) -> [f64; 4] {
+        let (c, m, y) = (1.0 - r, 1.0 - g, 1.0 - b);
+        let gray = c.min(m).min(y);
+
+        let k = match self.black_generation {
+            BlackGeneration::None => 0.0,
+            BlackGeneration::Ucr { amount } => {
+                let shadow = ((gray - 0.5) / 0.5).clamp(0.0, 1.0);
+                amount.clamp(0.0, 1.0) * gray * shadow
+            }
+            BlackGeneration::Gcr { amount } => amount.clamp(0.0, 1.0) * gray,
+        };
+        let (c, m, y) = (c - k, m - k, y - k);
+
+        limit_total_ink([c, m, y, k], self.total_ink_limit / 100.0)
+    }
+
+    pub fn separate_rgb8(&self, color: Rgb8Color) -> Cmyk8Color {
+        self.quantize(self.separate(
+            color.r as f64 / 255.0,
+            color.g as f64 / 255.0,
+            color.b as f64 / 255.0,
+        ))
+    }
+
+    /// 16位 RGB 按全精度分色为 CMYK 8位
+    pub fn separate_rgb16(&self, color: Rgb16Color) -> Cmyk8Color {
+        self.quantize(self.separate(
+            color.r as f64 / 65535.0,
+            color.g as f64 / 65535.0,
+            color.b as f64 / 65535.0,
+        ))
+    }
+
+    /// 取整为 8位，取整后总墨量仍超出上限时从 CMY 中最多的一色逐级扣减
+    fn quantize(&self, cmyk: [f64; 4]) -> Cmyk8Color {
+        let mut px = cmyk.map(|v| (v * 255.0).round().clamp(0.0, 255.0) as u8);
+        let limit = (self.total_ink_limit / 100.0 * 255.0)
+            .floor()
+            .clamp(0.0, 1020.0) as u32;
+        px[3] = px[3].min(limit.min(255) as u8);
+        while px.iter().map(|&v| v as u32).sum::<u32>() > limit {
+            let index = (0..3).max_by_key(|&i| px[i]).unwrap_or(0);
+            px[index] -= 1;
+        }
+        let [c, m, y, k] = px;
+        Cmyk8Color { c, m, y, k }
+    }
+}
+
+/// RGB 8位图像分色为 CMYK 8位
+pub fn rgb8_to_cmyk8(
+    img: &MatrixImage<Rgb8Color>,
+    separation: &CmykSeparation,
+) -> MatrixImage<Cmyk8Color> {
+    let mut out = MatrixImage::new(img.width(), img.height());
+    out.inner_mut()
+        .iter_mut()
+        .zip(img.inner().iter())
+        .for_each(|(dst, src)| *dst = separation.separate_rgb8(*src));
+    if let Some(info) = img.info() {
+        out.set_info(info.clone());
+    }
+    out
+}
+
+/// 将总墨量限制到 `limit`（0~4），保留黑版，按比例减少 CMY
+pub(crate) fn limit_total_ink([c, m, y, k]: [f64; 4], limit: f64) -> [f64; 4] {
+    let total = c + m + y + k;
+    if total <= limit {
+        return [c, m, y, k];
+    }
+    if k >= limit {
+        return [0.0, 0.0, 0.0, limit.max(0.0)];
+    }
+
+    let scale = (limit - k) / (c + m + y);
+    [c * scale, m * scale, y * scale, k]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_separation() {
+        let gcr = CmykSeparation {
+            black_generation: BlackGeneration::Gcr { amount: 1.0 },
+            total_ink_limit: 400.0,
+        };
+        // 中性灰完全由黑版承担
+        let [c, m, y, k] = gcr.separate(0.4, 0.4, 0.4);
+        assert!(c.abs() < 1e-9 && m.abs() < 1e-9 && y.abs() < 1e-9);
+        assert!((k - 0.6).abs() < 1e-9);
+
+        // UCR 不影响亮部
+        let ucr = CmykSeparation {
+            black_generation: BlackGeneration::Ucr { amount: 1.0 },
+            total_ink_limit: 400.0,
+        };
+        assert_eq!(ucr.separate(0.8, 0.8, 0.8)[3], 0.0);
+        assert!(ucr.separate(0.1, 0.1, 0.1)[3] > 0.0);
+
+        // 纯黑按墨量上限压缩 CMY
+        let limited = CmykSeparation {
+            black_generation: BlackGeneration::Gcr { amount: 0.1 },
+            total_ink_limit: 250.0,
+        };
+        let px = limited.separate(0.0, 0.0, 0.0);
+        assert!((px.iter().sum::<f64>() - 2.5).abs() < 1e-9);
+        assert!((px[3] - 0.1).abs() < 1e-9);
+
+        // 取整后总墨量不超过上限
+        for limit in [250.0, 260.0, 300.0] {
+            let separation = CmykSeparation {
+                total_ink_limit: limit,
+                ..Default::default()
+            };
+            for v in (0..=255).step_by(5) {
+                let px = separation.separate_rgb8(Rgb8Color { r: v, g: 0, b: 40 });
+                let total = px.c as u32 + px.m as u32 + px.y as u32 + px.k as u32;
+                assert!(total as f64 <= limit / 100.0 * 255.0, "total ink: {total}");
+            }
+        }
+
+        // 16位按全精度分色，与 8位结果一致
+        let rgb16 = Rgb16Color {
+            r: 0x8080,
+            g: 0x4040,
+            b: 0xffff,
+        };
+        let rgb8 = Rgb8Color {
+            r: 0x80,
+            g: 0x40,
+            b: 0xff,
+        };
+        assert_eq!(gcr.separate_rgb16(rgb16), gcr.separate_rgb8(rgb8));
+    }
+}
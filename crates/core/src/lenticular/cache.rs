@@ -0,0 +1,167 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use crate::image::{CmykSeparation, MatrixImage};
+
+use super::{FitMode, InterlaceColor, OutputInfo, ScaleAlgorithm};
+
+type FittedFrame = Arc<dyn Any + Send + Sync>;
+
+/// 已裁切、缩放到输出尺寸的帧缓存
+///
+/// 多次合成（如变量数据）中不变的帧按调用方指定的键只适配一次，
+/// 键见 [`InputImageContext::with_cache_key`](super::InputImageContext::with_cache_key)。
+/// 输出尺寸、适配方式、缩放算法或分色参数不同时分别缓存。
+///
+/// 缓存不淘汰条目：每个键按出现过的每组输出参数各保留一帧，逐次改变尺寸或适配方式
+/// （如变量数据中逐行覆盖 `width`/`height`/`fit`）时占用随之增长，直到缓存被释放。
+#[derive(Default)]
+pub struct FittedFrameCache {
+    frames: Mutex<HashMap<String, Vec<(FitSignature, FittedFrame)>>>,
+    hits: AtomicUsize,
+}
+
+impl FittedFrameCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 命中缓存的次数
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn get<C>(&self, key: &str, signature: &FitSignature) -> Option<Arc<MatrixImage<C>>>
+    where
+        C: InterlaceColor,
+    {
+        let frames = self.frames.lock().unwrap_or_else(PoisonError::into_inner);
+        let (_, frame) = frames
+            .get(key)?
+            .iter()
+            .find(|(cached, _)| cached == signature)?;
+        let frame = frame.clone().downcast::<MatrixImage<C>>().ok()?;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(frame)
+    }
+
+    pub(crate) fn insert<C>(&self, key: &str, signature: FitSignature, frame: Arc<MatrixImage<C>>)
+    where
+        C: InterlaceColor,
+    {
+        self.frames
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key.to_string())
+            .or_default()
+            .push((signature, frame));
+    }
+}
+
+/// 决定适配结果的输出参数
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FitSignature {
+    trim_width: u32,
+    trim_height: u32,
+    bleed_x: u32,
+    bleed_y: u32,
+    fit_mode: FitMode,
+    scale_alg: ScaleAlgorithm,
+    cmyk_separation: Option<CmykSeparation>,
+    color_type: tiff::ColorType,
+}
+
+impl FitSignature {
+    pub(crate) fn new<C>(output_info: &OutputInfo, scale_alg: ScaleAlgorithm) -> Self
+    where
+        C: InterlaceColor,
+    {
+        Self {
+            trim_width: output_info.layout.trim_width,
+            trim_height: output_info.layout.trim_height,
+            bleed_x: output_info.layout.bleed_x,
+            bleed_y: output_info.layout.bleed_y,
+            fit_mode: output_info.fit_mode,
+            scale_alg,
+            cmyk_separation: output_info.cmyk_separation,
+            color_type: C::COLOR_TYPE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        image::Cmyk8Color,
+        lenticular::{process_tiff_cached, test_util::input_frame, ProcessOptions},
+        units::Length,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_fitted_frame_cache() {
+        let frame =
+            |value: u8| input_frame(tiff::ColorType::CMYK(8), 4, 4, vec![value; 4 * 4 * 4], 1);
+        // 第一帧为静态帧，第二帧每次不同
+        let inputs = |static_value: u8, variable_value: u8| {
+            vec![
+                frame(static_value).with_cache_key("static"),
+                frame(variable_value),
+            ]
+        };
+
+        let cache = FittedFrameCache::new();
+        let opt = ProcessOptions::new(8.0, Length::inches(1.0));
+        let output_info = opt.calc_output_info(&mut inputs(10, 20)).unwrap();
+        let first: MatrixImage<Cmyk8Color> = process_tiff_cached(
+            inputs(10, 20),
+            &output_info,
+            ScaleAlgorithm::Nearest,
+            Some(&cache),
+        )
+        .unwrap();
+        assert_eq!(cache.hits(), 0);
+        assert!(first.inner().iter().any(|c| c.c == 10));
+
+        // 静态帧使用缓存，不再读取新的数据；其他帧照常处理
+        let second: MatrixImage<Cmyk8Color> = process_tiff_cached(
+            inputs(99, 30),
+            &output_info,
+            ScaleAlgorithm::Nearest,
+            Some(&cache),
+        )
+        .unwrap();
+        assert_eq!(cache.hits(), 1);
+        assert!(second.inner().iter().all(|c| c.c == 10 || c.c == 30));
+
+        // 缩放算法不同时重新适配
+        let _: MatrixImage<Cmyk8Color> = process_tiff_cached(
+            inputs(10, 20),
+            &output_info,
+            ScaleAlgorithm::Bilinear,
+            Some(&cache),
+        )
+        .unwrap();
+        assert_eq!(cache.hits(), 1);
+
+        // 其他合成中 panic 导致锁中毒后仍可读取缓存
+        let _ = std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let _frames = cache.frames.lock().unwrap();
+                    panic!("模拟合成 panic");
+                })
+                .join()
+        });
+        let signature = FitSignature::new::<Cmyk8Color>(&output_info, ScaleAlgorithm::Nearest);
+        assert!(cache.get::<Cmyk8Color>("static", &signature).is_some());
+        assert_eq!(cache.hits(), 2);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    error::{Error, Result},
+    image::{Cmyk8Color, DpiInfo, MatrixImage},
+    units::Length,
+};
+
+/// 透镜节距测试图参数
+#[derive(Debug, Clone)]
+pub struct PitchTestOptions {
+    /// 标称 LPI
+    pub lpi: f64,
+    /// 打印机 DPI
+    pub dpi: f64,
+    /// 相邻色带的 LPI 差值
+    pub step: f64,
+    /// 标称值两侧各生成的色带数
+    pub steps: u32,
+    /// 测试图宽度
+    pub width: Length,
+    /// 每条色带的高度
+    pub band_height: Length,
+}
+
+impl PitchTestOptions {
+    pub fn new(lpi: f64, dpi: f64, width: Length) -> Self {
+        Self {
+            lpi,
+            dpi,
+            step: 0.1,
+            steps: 5,
+            width,
+            band_height: Length::mm(5.0),
+        }
+    }
+
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    pub fn with_steps(mut self, steps: u32) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    pub fn with_band_height(mut self, band_height: Length) -> Self {
+        self.band_height = band_height;
+        self
+    }
+}
+
+/// 测试图中的一条色带
+#[derive(Debug, Clone, PartialEq)]
+pub struct PitchTestBand {
+    pub lpi: f64,
+    /// 色带顶边，单位像素
+    pub y: u32,
+    pub height: u32,
+}
+
+/// 节距测试图，色带自上而下按 LPI 递增排列
+pub struct PitchTestChart {
+    pub image: MatrixImage<Cmyk8Color>,
+    pub bands: Vec<PitchTestBand>,
+}
+
+/// 生成透镜节距测试图
+///
+/// 每条色带为按对应 LPI 排列的黑白线条（各占半个节距）。打印后透过透镜观察，
+/// 整条显示为均匀色块、不出现明暗条纹的色带即为实际节距。
+/// 标称 LPI 的色带左侧有实心标记块，色带之间留白。
+pub fn pitch_test_chart(opts: &PitchTestOptions) -> Result<PitchTestChart> {
+    if opts.lpi <= 0.0 || opts.dpi <= 0.0 || opts.step <= 0.0 {
+        return Err(Error::InvalidInput("LPI、DPI 与步长必须大于 0".to_string()));
+    }
+    if opts.lpi - opts.step * opts.steps as f64 <= 0.0 {
+        return Err(Error::InvalidInput(format!(
+            "最小 LPI {:.3} 必须大于 0",
+            opts.lpi - opts.step * opts.steps as f64
+        )));
+    }
+    let width = opts.width.to_px(opts.dpi).round() as u32;
+    let band_height = opts.band_height.to_px(opts.dpi).round() as u32;
+    // 标记块为正方形，宽度与色带高度相同
+    if band_height == 0 || width <= band_height * 2 {
+        return Err(Error::InvalidInput(format!(
+            "测试图宽度 {} 像素或色带高度 {} 像素过小",
+            width, band_height
+        )));
+    }
+    let gap = (band_height / 4).max(1);
+    let count = opts.steps * 2 + 1;
+    let height = count * band_height + (count + 1) * gap;
+
+    let black = Cmyk8Color {
+        c: 0,
+        m: 0,
+        y: 0,
+        k: 255,
+    };
+    let mut image = MatrixImage::<Cmyk8Color>::new(width, height);
+    let mut bands = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let offset = index as f64 - opts.steps as f64;
+        let lpi = opts.lpi + offset * opts.step;
+        let y = gap + index * (band_height + gap);
+        let mat = image.inner_mut();
+        for x in band_height * 2..width {
+            let phase = ((x as f64 + 0.5) * lpi / opts.dpi).fract();
+            if phase < 0.5 {
+                for row in y..y + band_height {
+                    mat[[row as usize, x as usize]] = black;
+                }
+            }
+        }
+        if offset == 0.0 {
+            for row in y..y + band_height {
+                for x in 0..band_height {
+                    mat[[row as usize, x as usize]] = black;
+                }
+            }
+        }
+        bands.push(PitchTestBand {
+            lpi,
+            y,
+            height: band_height,
+        });
+    }
+    image.set_info(DpiInfo {
+        dpi_h: opts.dpi,
+        dpi_w: opts.dpi,
+    });
+    Ok(PitchTestChart { image, bands })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pitch_test_chart() {
+        let opts = PitchTestOptions::new(40.0, 400.0, Length::inches(1.0))
+            .with_step(0.5)
+            .with_steps(2)
+            .with_band_height(Length::inches(0.05));
+        let chart = pitch_test_chart(&opts).unwrap();
+        assert_eq!(chart.image.width(), 400);
+        let lpis = chart.bands.iter().map(|band| band.lpi).collect::<Vec<_>>();
+        assert_eq!(lpis, vec![39.0, 39.5, 40.0, 40.5, 41.0]);
+        // 色带高 20 像素，间隔 5 像素
+        assert_eq!(chart.bands[1].y, 30);
+        assert_eq!(chart.image.height(), 5 * 20 + 6 * 5);
+
+        // 标称色带：节距 10 像素，每个节距前 5 像素为黑
+        let band = &chart.bands[2];
+        let row = chart.image.inner().row(band.y as usize);
+        let line = (40..60).map(|x| row[x].k).collect::<Vec<_>>();
+        assert_eq!(line[..5], [255; 5]);
+        assert_eq!(line[5..10], [0; 5]);
+        assert_eq!(line[10..15], [255; 5]);
+        // 只有标称色带有标记块
+        assert_eq!(row[0].k, 255);
+        let row = chart.image.inner().row(chart.bands[1].y as usize);
+        assert_eq!(row[0].k, 0);
+        // 色带之间留白
+        let row = chart.image.inner().row(band.y as usize - 1);
+        assert!(row.iter().all(|c| c.k == 0));
+
+        assert!(pitch_test_chart(&opts.clone().with_steps(80)).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+use ndarray::Axis;
+
+use crate::{
+    decoder::FrameDecoder,
+    error::{Error, Result},
+    image::{DpiInfo, MatrixImage},
+};
+
+use super::{column_frame_indices, InterlaceColor, ScaleAlgorithm};
+
+/// 读取整幅图像，保留分辨率与 ICC 配置文件，未记录分辨率时图像不带分辨率信息
+pub fn read_image<C, D>(decoder: &mut D) -> Result<MatrixImage<C>>
+where
+    C: InterlaceColor,
+    D: FrameDecoder,
+{
+    let params = decoder.read_params(true)?;
+    if params.color_type != Some(C::COLOR_TYPE) {
+        return Err(Error::InvalidInput(format!(
+            "非预期的颜色类型 {:?}，仅接受 {:?} 图像",
+            params.color_type,
+            C::COLOR_TYPE
+        )));
+    }
+    let Some(data) = C::raw_bytes(decoder.read_image()?) else {
+        return Err(Error::InvalidInput(format!(
+            "图像数据读取失败: 非预期的编码类型，仅接受 {:?} 图像",
+            C::COLOR_TYPE
+        )));
+    };
+    let mut img = MatrixImage::from_slice(&data, params.width, params.height)?;
+    if let Some((dpi_w, dpi_h)) = params.dpi() {
+        img.set_info(DpiInfo { dpi_h, dpi_w });
+    }
+    img.set_icc_profile(params.icc_profile);
+    Ok(img)
+}
+
+/// 将光栅图按透镜内各帧的条纹宽度拆回各帧
+///
+/// `lenticular_widths` 按条纹在透镜内的顺序排列，图像左边缘须与透镜边缘对齐。
+/// 指定 `restore` 时用该算法将各帧拉伸回原图宽度，否则各帧宽度为其条纹列数之和。
+pub fn deinterlace<C>(
+    img: &MatrixImage<C>,
+    lenticular_widths: &[u32],
+    restore: Option<ScaleAlgorithm>,
+) -> Result<Vec<MatrixImage<C>>>
+where
+    C: InterlaceColor,
+{
+    if lenticular_widths.is_empty() || lenticular_widths.contains(&0) {
+        return Err(Error::InvalidInput("条纹宽度必须大于 0".to_string()));
+    }
+    let pitch = lenticular_widths.iter().sum::<u32>();
+    if img.width() < pitch {
+        return Err(Error::InvalidInput(format!(
+            "图像宽度 {} 像素小于一个透镜的宽度 {} 像素",
+            img.width(),
+            pitch
+        )));
+    }
+
+    let indices = column_frame_indices(img.width(), lenticular_widths);
+    (0..lenticular_widths.len())
+        .map(|frame_index| {
+            let columns = indices
+                .iter()
+                .enumerate()
+                .filter(|(_, &index)| index == frame_index)
+                .map(|(col, _)| col)
+                .collect::<Vec<_>>();
+            let mat = img.inner().select(Axis(1), &columns);
+            let mut frame = MatrixImage::<C>::new(columns.len() as u32, img.height());
+            *frame.inner_mut() = mat;
+            let mut frame = match restore {
+                Some(alg) => restore_width(frame, img.width(), alg)?,
+                None => frame,
+            };
+            if let Some(info) = img.info() {
+                frame.set_info(DpiInfo {
+                    dpi_h: info.dpi_h,
+                    dpi_w: info.dpi_w * frame.width() as f64 / img.width() as f64,
+                });
+            }
+            frame.set_icc_profile(img.icc_profile().map(<[u8]>::to_vec));
+            Ok(frame)
+        })
+        .collect()
+}
+
+fn restore_width<C>(
+    frame: MatrixImage<C>,
+    width: u32,
+    alg: ScaleAlgorithm,
+) -> Result<MatrixImage<C>>
+where
+    C: InterlaceColor,
+{
+    let data = C::resize(
+        C::image_bytes(&frame),
+        frame.width(),
+        frame.height(),
+        width,
+        frame.height(),
+        alg,
+    )?;
+    MatrixImage::from_slice(&data, width, frame.height())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{image::Cmyk8Color, lenticular::test_util::tiff_with_resolution};
+
+    use super::*;
+
+    #[test]
+    fn test_deinterlace() {
+        // 每个透镜 3 列：帧 0 占 2 列，帧 1 占 1 列，共 2 个透镜
+        let mut img = MatrixImage::<Cmyk8Color>::new(6, 2);
+        let mat = img.inner_mut();
+        for (col, value) in [10, 11, 20, 12, 13, 21].into_iter().enumerate() {
+            mat.column_mut(col).fill(Cmyk8Color {
+                c: value,
+                m: 0,
+                y: 0,
+                k: 0,
+            });
+        }
+        img.set_info(DpiInfo {
+            dpi_h: 300.0,
+            dpi_w: 300.0,
+        });
+
+        let frames = deinterlace(&img, &[2, 1], None).unwrap();
+        assert_eq!(frames.len(), 2);
+        let values = |frame: &MatrixImage<Cmyk8Color>| {
+            frame.inner().row(0).iter().map(|c| c.c).collect::<Vec<_>>()
+        };
+        assert_eq!(values(&frames[0]), vec![10, 11, 12, 13]);
+        assert_eq!(values(&frames[1]), vec![20, 21]);
+        assert_eq!(frames[1].height(), 2);
+        assert_eq!(frames[1].info().unwrap().dpi_w, 100.0);
+
+        let frames = deinterlace(&img, &[2, 1], Some(ScaleAlgorithm::Nearest)).unwrap();
+        assert!(frames.iter().all(|frame| frame.width() == 6));
+        assert_eq!(frames[1].info().unwrap().dpi_w, 300.0);
+
+        assert!(deinterlace(&img, &[4, 3], None).is_err());
+        assert!(deinterlace(&img, &[2, 0], None).is_err());
+
+        // 未记录分辨率的 TIFF 也可读取和拆分
+        let img = read_image::<Cmyk8Color, _>(&mut tiff_with_resolution(&[])).unwrap();
+        assert!(img.info().is_none());
+        let frames = deinterlace(&img, &[1, 1], None).unwrap();
+        assert!(frames.iter().all(|frame| frame.info().is_none()));
+    }
+}
@@ -0,0 +1,309 @@
+use std::fmt::Write;
+
+use crate::units::Length;
+
+use super::OutputInfo;
+
+/// 圆角按该角度步长折线化，单位：度
+const ARC_STEP_DEGREES: f64 = 5.0;
+
+/// 模切形状，坐标以成品左上角为原点
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CutShape {
+    /// 沿成品边缘的矩形
+    #[default]
+    Rectangle,
+    /// 圆角矩形
+    RoundedRectangle { radius: Length },
+    /// 自定义闭合多边形，各点为相对成品左上角的 (x, y)，y 轴向下
+    Custom(Vec<[Length; 2]>),
+}
+
+/// 与光栅图像对齐的模切文件，单位：毫米
+///
+/// 坐标系与 `write_tiff_cmyk8` 写出的图像一致（含标记区域），原点在左上角，y 轴向下。
+#[derive(Debug, Clone, PartialEq)]
+pub struct DieCut {
+    pub width: f64,
+    pub height: f64,
+    /// 闭合的切割路径
+    pub cut_path: Vec<(f64, f64)>,
+    /// 与印刷一致的裁切标记线段
+    pub marks: Vec<[(f64, f64); 2]>,
+}
+
+/// 按输出图像信息计算模切路径与裁切标记
+///
+/// `with_marks` 为写出的图像是否绘制了标记（仅 CMYK 8位输出绘制），
+/// 为 `false` 时坐标不含标记区域，也不输出裁切标记。
+pub fn die_cut(output_info: &OutputInfo, shape: &CutShape, with_marks: bool) -> DieCut {
+    let layout = &output_info.layout;
+    let mm_x = |px: u32| Length::from_px(px as f64, output_info.dpi_w).to_mm();
+    let mm_y = |px: u32| Length::from_px(px as f64, output_info.dpi_h).to_mm();
+
+    // 仅在写出时绘制标记的情况下图像四周才有标记区域
+    let with_marks = with_marks && layout.has_marks();
+    let slug = if with_marks { layout.slug() } else { 0 };
+    let left = slug + layout.bleed_x;
+    let top = slug + layout.bleed_y;
+    let (x0, y0) = (mm_x(left), mm_y(top));
+    let (w, h) = (mm_x(layout.trim_width), mm_y(layout.trim_height));
+
+    let cut_path = match shape {
+        CutShape::Rectangle => vec![(x0, y0), (x0 + w, y0), (x0 + w, y0 + h), (x0, y0 + h)],
+        CutShape::RoundedRectangle { radius } => {
+            rounded_rectangle(x0, y0, w, h, radius.to_mm().clamp(0.0, w.min(h) / 2.0))
+        }
+        CutShape::Custom(points) => points
+            .iter()
+            .map(|[x, y]| (x0 + x.to_mm(), y0 + y.to_mm()))
+            .collect(),
+    };
+
+    let mut marks = vec![];
+    if let Some(crop_marks) = layout.crop_marks.filter(|_| with_marks) {
+        let (width, height) = (layout.bleed_width(), layout.bleed_height());
+        let (offset, length) = (crop_marks.offset, crop_marks.length);
+        let right = left + layout.trim_width;
+        let bottom = top + layout.trim_height;
+        let h_ranges = [
+            (slug - offset - length, slug - offset),
+            (slug + width + offset, slug + width + offset + length),
+        ];
+        let v_ranges = [
+            (slug - offset - length, slug - offset),
+            (slug + height + offset, slug + height + offset + length),
+        ];
+        for y in [top, bottom] {
+            for (start, end) in h_ranges {
+                marks.push([(mm_x(start), mm_y(y)), (mm_x(end), mm_y(y))]);
+            }
+        }
+        for x in [left, right] {
+            for (start, end) in v_ranges {
+                marks.push([(mm_x(x), mm_y(start)), (mm_x(x), mm_y(end))]);
+            }
+        }
+    }
+
+    DieCut {
+        width: mm_x(layout.bleed_width() + slug * 2),
+        height: mm_y(layout.bleed_height() + slug * 2),
+        cut_path,
+        marks,
+    }
+}
+
+/// 顺时针生成圆角矩形的折线
+fn rounded_rectangle(x: f64, y: f64, w: f64, h: f64, r: f64) -> Vec<(f64, f64)> {
+    if r <= 0.0 {
+        return vec![(x, y), (x + w, y), (x + w, y + h), (x, y + h)];
+    }
+
+    let steps = (90.0 / ARC_STEP_DEGREES).ceil() as usize;
+    // 四个圆角的圆心与起始角度（y 轴向下，角度顺时针增加）
+    let corners = [
+        (x + w - r, y + r, 270.0),
+        (x + w - r, y + h - r, 0.0),
+        (x + r, y + h - r, 90.0),
+        (x + r, y + r, 180.0),
+    ];
+    corners
+        .iter()
+        .flat_map(|&(cx, cy, start)| {
+            (0..=steps).map(move |i| {
+                let angle = (start + 90.0 * i as f64 / steps as f64).to_radians();
+                (cx + r * angle.cos(), cy + r * angle.sin())
+            })
+        })
+        .collect()
+}
+
+impl DieCut {
+    /// 输出 SVG，切割路径与裁切标记分别位于 `cut` 与 `registration` 图层
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        let _ = writeln!(svg, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}mm" height="{h}mm" viewBox="0 0 {w} {h}">"#,
+            w = fmt_mm(self.width),
+            h = fmt_mm(self.height)
+        );
+
+        let points = self
+            .cut_path
+            .iter()
+            .map(|(x, y)| format!("{},{}", fmt_mm(*x), fmt_mm(*y)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(
+            svg,
+            r##"  <g id="cut" fill="none" stroke="#ff0000" stroke-width="0.1">"##
+        );
+        let _ = writeln!(svg, r#"    <polygon points="{}"/>"#, points);
+        let _ = writeln!(svg, "  </g>");
+
+        if !self.marks.is_empty() {
+            let _ = writeln!(
+                svg,
+                r##"  <g id="registration" fill="none" stroke="#000000" stroke-width="0.1">"##
+            );
+            for [(x1, y1), (x2, y2)] in &self.marks {
+                let _ = writeln!(
+                    svg,
+                    r#"    <line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                    fmt_mm(*x1),
+                    fmt_mm(*y1),
+                    fmt_mm(*x2),
+                    fmt_mm(*y2)
+                );
+            }
+            let _ = writeln!(svg, "  </g>");
+        }
+
+        let _ = writeln!(svg, "</svg>");
+        svg
+    }
+
+    /// 输出 DXF（R12），切割路径与裁切标记分别位于 `CUT` 与 `REGISTRATION` 图层
+    ///
+    /// 坐标单位为毫米（R12 没有单位变量，导入时需按毫米处理）。
+    /// DXF 的 y 轴向上，原点在图像左下角。
+    pub fn to_dxf(&self) -> String {
+        let mut dxf = String::new();
+        let mut pair = |code: u32, value: &str| {
+            let _ = writeln!(dxf, "{}\n{}", code, value);
+        };
+        let flip = |y: f64| self.height - y;
+
+        pair(0, "SECTION");
+        pair(2, "HEADER");
+        pair(9, "$ACADVER");
+        pair(1, "AC1009");
+        pair(0, "ENDSEC");
+
+        pair(0, "SECTION");
+        pair(2, "ENTITIES");
+        pair(0, "POLYLINE");
+        pair(8, "CUT");
+        pair(66, "1");
+        // R12 的 POLYLINE 须带一个占位点，顶点坐标由后续 VERTEX 给出
+        pair(10, "0.0");
+        pair(20, "0.0");
+        pair(30, "0.0");
+        pair(70, "1");
+        for (x, y) in &self.cut_path {
+            pair(0, "VERTEX");
+            pair(8, "CUT");
+            pair(10, &fmt_mm(*x));
+            pair(20, &fmt_mm(flip(*y)));
+            pair(30, "0.0");
+        }
+        pair(0, "SEQEND");
+        pair(8, "CUT");
+        for [(x1, y1), (x2, y2)] in &self.marks {
+            pair(0, "LINE");
+            pair(8, "REGISTRATION");
+            pair(10, &fmt_mm(*x1));
+            pair(20, &fmt_mm(flip(*y1)));
+            pair(30, "0.0");
+            pair(11, &fmt_mm(*x2));
+            pair(21, &fmt_mm(flip(*y2)));
+            pair(31, "0.0");
+        }
+        pair(0, "ENDSEC");
+        pair(0, "EOF");
+
+        dxf
+    }
+}
+
+fn fmt_mm(value: f64) -> String {
+    format!("{:.4}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lenticular::{CropMarks, FitMode, PageLayout, SourceParams};
+
+    use super::*;
+
+    #[test]
+    fn test_die_cut_aligned_to_trim() {
+        // 254 DPI：10 像素 = 1mm
+        let output_info = OutputInfo {
+            width: 100,
+            height: 60,
+            dpi_w: 254.0,
+            dpi_h: 254.0,
+            physical_width: Length::mm(8.0),
+            physical_height: Length::mm(4.0),
+            layout: PageLayout {
+                trim_width: 80,
+                trim_height: 40,
+                bleed_x: 10,
+                bleed_y: 10,
+                crop_marks: Some(CropMarks {
+                    offset: 10,
+                    length: 50,
+                    thickness: 1,
+                }),
+                ..Default::default()
+            },
+            icc_override: None,
+            cmyk_separation: None,
+            lzw: false,
+            fit_mode: FitMode::Stretch,
+            frame_sizes: vec![],
+            source_params: SourceParams::default(),
+        };
+
+        let cut = die_cut(&output_info, &CutShape::Rectangle, true);
+        assert!((cut.width - 22.0).abs() < 1e-9);
+        // 成品左上角位于标记区域 6mm + 出血 1mm 处
+        assert!((cut.cut_path[0].0 - 7.0).abs() < 1e-9);
+        assert!((cut.cut_path[2].1 - 11.0).abs() < 1e-9);
+        assert_eq!(cut.marks.len(), 8);
+        assert!((cut.marks[0][0].1 - 7.0).abs() < 1e-9);
+
+        let rounded = die_cut(
+            &output_info,
+            &CutShape::RoundedRectangle {
+                radius: Length::mm(1.0),
+            },
+            true,
+        );
+        assert!(rounded
+            .cut_path
+            .iter()
+            .all(|(x, y)| (7.0 - 1e-9..=15.0 + 1e-9).contains(x)
+                && (7.0 - 1e-9..=11.0 + 1e-9).contains(y)));
+
+        assert!(cut.to_svg().contains(r#"<g id="cut""#));
+        let dxf = cut.to_dxf();
+        assert!(dxf.contains("REGISTRATION"));
+        assert!(!dxf.contains("$INSUNITS"));
+        // POLYLINE 的占位点位于顶点之前
+        let polyline = &dxf[dxf.find("POLYLINE").unwrap()..dxf.find("VERTEX").unwrap()];
+        assert!(polyline.contains("\n10\n0.0\n20\n0.0\n30\n0.0\n"));
+
+        // 未绘制标记的输出（如 RGB）不含标记区域
+        let plain = die_cut(&output_info, &CutShape::Rectangle, false);
+        assert!((plain.width - 10.0).abs() < 1e-9);
+        assert!((plain.cut_path[0].0 - 1.0).abs() < 1e-9);
+        assert!(plain.marks.is_empty());
+
+        let custom = die_cut(
+            &output_info,
+            &CutShape::Custom(vec![
+                [Length::mm(0.0), Length::mm(0.0)],
+                [Length::mm(8.0), Length::mm(2.0)],
+                [Length::mm(0.0), Length::mm(4.0)],
+            ]),
+            false,
+        );
+        assert_eq!(custom.cut_path.len(), 3);
+        assert!((custom.cut_path[1].0 - 9.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,295 @@
+use ndarray::s;
+
+use crate::image::{Cmyk8Color, Color, MatrixImage};
+
+pub use crate::image::{CropMarks, PageLayout, RegistrationBars};
+
+use super::create_line_index_mapping_advanced;
+
+/// 套准黑（四色满版），所有印版上都可见
+pub(crate) const REGISTRATION_BLACK: Cmyk8Color = Cmyk8Color {
+    c: 255,
+    m: 255,
+    y: 255,
+    k: 255,
+};
+/// 安全边距参考线颜色（品红）
+const SAFE_MARGIN_COLOR: Cmyk8Color = Cmyk8Color {
+    c: 0,
+    m: 255,
+    y: 0,
+    k: 0,
+};
+/// 对位条中按帧序号循环使用的颜色：C、M、Y、CM、MY、CY
+const FRAME_COLORS: [Cmyk8Color; 6] = [
+    Cmyk8Color {
+        c: 255,
+        m: 0,
+        y: 0,
+        k: 0,
+    },
+    Cmyk8Color {
+        c: 0,
+        m: 255,
+        y: 0,
+        k: 0,
+    },
+    Cmyk8Color {
+        c: 0,
+        m: 0,
+        y: 255,
+        k: 0,
+    },
+    Cmyk8Color {
+        c: 255,
+        m: 255,
+        y: 0,
+        k: 0,
+    },
+    Cmyk8Color {
+        c: 0,
+        m: 255,
+        y: 255,
+        k: 0,
+    },
+    Cmyk8Color {
+        c: 255,
+        m: 0,
+        y: 255,
+        k: 0,
+    },
+];
+
+/// 在光栅合成图像外围加上标记区域，并绘制裁切标记与安全边距参考线
+///
+/// `img` 的尺寸应为含出血的光栅合成区域尺寸。
+pub fn apply_print_marks(
+    img: &MatrixImage<Cmyk8Color>,
+    layout: &PageLayout,
+) -> MatrixImage<Cmyk8Color> {
+    let slug = layout.slug() as usize;
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    let mut out = pad_slug(img, layout);
+
+    // 成品边缘在输出图像中的位置
+    let left = slug + layout.bleed_x as usize;
+    let top = slug + layout.bleed_y as usize;
+    let right = left + layout.trim_width as usize;
+    let bottom = top + layout.trim_height as usize;
+
+    if let Some(margin) = layout.safe_margin {
+        let margin = margin as usize;
+        let (l, t) = (left + margin, top + margin);
+        let (r, b) = (
+            right.saturating_sub(margin + 1),
+            bottom.saturating_sub(margin + 1),
+        );
+        if l < r && t < b {
+            let mat = out.inner_mut();
+            mat.slice_mut(s![t, l..=r]).fill(SAFE_MARGIN_COLOR);
+            mat.slice_mut(s![b, l..=r]).fill(SAFE_MARGIN_COLOR);
+            mat.slice_mut(s![t..=b, l]).fill(SAFE_MARGIN_COLOR);
+            mat.slice_mut(s![t..=b, r]).fill(SAFE_MARGIN_COLOR);
+        }
+    }
+
+    if let Some(marks) = layout.crop_marks {
+        let mat = out.inner_mut();
+        let (offset, length, thickness) = (
+            marks.offset as usize,
+            marks.length as usize,
+            marks.thickness as usize,
+        );
+        // 水平标记画在左右两侧的标记区域，垂直标记画在上下两侧
+        let h_ranges = [
+            slug - offset - length..slug - offset,
+            slug + width + offset..slug + width + offset + length,
+        ];
+        let v_ranges = [
+            slug - offset - length..slug - offset,
+            slug + height + offset..slug + height + offset + length,
+        ];
+        for y in [top, bottom] {
+            let rows = y.saturating_sub(thickness / 2)..(y + thickness.div_ceil(2)).max(y + 1);
+            for cols in &h_ranges {
+                mat.slice_mut(s![rows.clone(), cols.clone()])
+                    .fill(REGISTRATION_BLACK);
+            }
+        }
+        for x in [left, right] {
+            let cols = x.saturating_sub(thickness / 2)..(x + thickness.div_ceil(2)).max(x + 1);
+            for rows in &v_ranges {
+                mat.slice_mut(s![rows.clone(), cols.clone()])
+                    .fill(REGISTRATION_BLACK);
+            }
+        }
+    }
+
+    if let Some(bars) = layout.registration_bars {
+        let frames = column_frame_indices(width as u32, &layout.lenticular_widths);
+        let lenticular_width = layout.lenticular_widths.iter().sum::<u32>().max(1) as usize;
+        let (offset, bar_height) = (bars.offset as usize, bars.height as usize);
+        let line_height = bar_height / 2;
+        // 避开裁切标记
+        let clearance = layout
+            .crop_marks
+            .map(|marks| marks.thickness as usize)
+            .unwrap_or(0);
+        let columns = left + clearance..right.saturating_sub(clearance);
+
+        // 上侧：外侧为光栅线，内侧为帧色条；下侧对称
+        let top_start = slug - offset - bar_height;
+        let bottom_start = slug + height + offset;
+        let sides = [
+            (
+                top_start..top_start + line_height,
+                top_start + line_height..slug - offset,
+            ),
+            (
+                bottom_start + bar_height - line_height..bottom_start + bar_height,
+                bottom_start..bottom_start + bar_height - line_height,
+            ),
+        ];
+
+        let mat = out.inner_mut();
+        for (line_rows, color_rows) in sides {
+            for x in columns.clone() {
+                let col = x - slug;
+                let position = frames[col];
+                // 帧色条按输入帧着色，背面印刷时光栅内的位置与帧顺序相反
+                let frame = if layout.reverse_print {
+                    layout.lenticular_widths.len() - 1 - position
+                } else {
+                    position
+                };
+                let color = FRAME_COLORS[frame % FRAME_COLORS.len()];
+                mat.slice_mut(s![color_rows.clone(), x]).fill(color);
+
+                // 每个光栅的第一列
+                if col.is_multiple_of(lenticular_width) {
+                    mat.slice_mut(s![line_rows.clone(), x])
+                        .fill(REGISTRATION_BLACK);
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// 在图像四周补上与标记区域等宽的空白，保留分辨率与 ICC 配置文件
+pub(crate) fn pad_slug<C>(img: &MatrixImage<C>, layout: &PageLayout) -> MatrixImage<C>
+where
+    C: Color,
+{
+    let slug = layout.slug() as usize;
+    let (width, height) = (img.width() as usize, img.height() as usize);
+
+    let mut out: MatrixImage<C> =
+        MatrixImage::new((width + slug * 2) as u32, (height + slug * 2) as u32);
+    out.inner_mut()
+        .slice_mut(s![slug..slug + height, slug..slug + width])
+        .assign(img.inner());
+    if let Some(info) = img.info() {
+        out.set_info(info.clone());
+    }
+    out.set_icc_profile(img.icc_profile().map(|p| p.to_vec()));
+    out.set_lzw(img.lzw());
+    out
+}
+
+/// 光栅合成图像中每一列在光栅内对应的帧位置
+pub(crate) fn column_frame_indices(width: u32, lenticular_widths: &[u32]) -> Vec<usize> {
+    let mut frames = vec![0; width as usize];
+    for img_index in 0..lenticular_widths.len() {
+        for col in create_line_index_mapping_advanced(width, lenticular_widths, img_index) {
+            if col < width {
+                frames[col as usize] = img_index;
+            }
+        }
+    }
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_print_marks() {
+        let layout = PageLayout {
+            trim_width: 20,
+            trim_height: 10,
+            bleed_x: 4,
+            bleed_y: 3,
+            safe_margin: Some(2),
+            crop_marks: Some(CropMarks {
+                offset: 1,
+                length: 5,
+                thickness: 1,
+            }),
+            ..Default::default()
+        };
+        let img: MatrixImage<Cmyk8Color> =
+            MatrixImage::new(layout.bleed_width(), layout.bleed_height());
+        let out = apply_print_marks(&img, &layout);
+        assert_eq!((out.width(), out.height()), (28 + 12, 16 + 12));
+
+        let mat = out.inner();
+        // 左上角：成品边缘 (x=10, y=9) 延长线上的标记，不进入出血区域
+        assert_eq!(mat[[9, 0]], REGISTRATION_BLACK);
+        assert_eq!(mat[[9, 4]], REGISTRATION_BLACK);
+        assert_eq!(mat[[9, 5]], Cmyk8Color::default());
+        assert_eq!(mat[[0, 10]], REGISTRATION_BLACK);
+        assert_eq!(mat[[6, 10]], Cmyk8Color::default());
+        // 右下角
+        assert_eq!(mat[[19, 39]], REGISTRATION_BLACK);
+        assert_eq!(mat[[27, 30]], REGISTRATION_BLACK);
+        // 安全边距参考线
+        assert_eq!(mat[[11, 12]], SAFE_MARGIN_COLOR);
+        assert_eq!(mat[[12, 13]], Cmyk8Color::default());
+    }
+
+    #[test]
+    fn test_registration_bars() {
+        let layout = PageLayout {
+            trim_width: 12,
+            trim_height: 4,
+            registration_bars: Some(RegistrationBars {
+                offset: 1,
+                height: 4,
+            }),
+            lenticular_widths: vec![1, 2],
+            ..Default::default()
+        };
+        let img: MatrixImage<Cmyk8Color> =
+            MatrixImage::new(layout.bleed_width(), layout.bleed_height());
+        let out = apply_print_marks(&img, &layout);
+        assert_eq!(layout.slug(), 5);
+
+        let mat = out.inner();
+        // 上侧光栅线：每 3 列一条
+        let lines = (0..12)
+            .filter(|x| mat[[0, 5 + x]] == REGISTRATION_BLACK)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [0, 3, 6, 9]);
+        // 帧色条：第 0 帧 1 列、第 1 帧 2 列
+        assert_eq!(mat[[3, 5]], FRAME_COLORS[0]);
+        assert_eq!(mat[[3, 6]], FRAME_COLORS[1]);
+        assert_eq!(mat[[3, 7]], FRAME_COLORS[1]);
+        // 下侧对称
+        assert_eq!(mat[[10, 6]], FRAME_COLORS[1]);
+        assert_eq!(mat[[13, 8]], REGISTRATION_BLACK);
+
+        // 单帧时每个光栅仍各有一条光栅线
+        let layout = PageLayout {
+            lenticular_widths: vec![3],
+            ..layout
+        };
+        let out = apply_print_marks(&img, &layout);
+        let lines = (0..12)
+            .filter(|x| out.inner()[[0, 5 + x]] == REGISTRATION_BLACK)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [0, 3, 6, 9]);
+    }
+}
@@ -0,0 +1,377 @@
+use log::{debug, warn};
+use ndarray::s;
+
+use crate::{
+    error::{Error, Result},
+    image::{Cmyk8Color, DpiInfo, MatrixImage},
+    units::Length,
+};
+
+use super::{CropMarks, PageLayout, REGISTRATION_BLACK};
+
+/// 拼版选项
+#[derive(Debug, Clone)]
+pub struct ImpositionOptions {
+    /// 光栅板线数，整张纸按每个光栅恰好 1/lpi 英寸的分辨率排版
+    pub lpi: f64,
+    pub sheet_width: Length,
+    pub sheet_height: Length,
+    /// 成品之间的间距，水平方向向上取整到整数个光栅
+    pub gutter: Length,
+    /// 纸张四周不放置成品的边距
+    pub margin: Length,
+    /// 最多放置的成品数量，不设置时排满整张纸
+    pub copies: Option<usize>,
+    /// 在成品区域外围绘制裁切标记
+    pub crop_marks: bool,
+}
+
+/// 拼版方案，单位均为像素
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpositionPlan {
+    /// 拼版分辨率，为光栅板线数与光栅像素宽度之积
+    pub dpi: f64,
+    pub sheet_width: u32,
+    pub sheet_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+    /// 第一个成品左上角（成品边缘）的位置
+    pub origin_x: u32,
+    pub origin_y: u32,
+    pub gutter_x: u32,
+    pub gutter_y: u32,
+    /// 实际放置的成品数量
+    pub copies: usize,
+}
+
+impl ImpositionPlan {
+    /// 第 `index` 个成品左上角（成品边缘）的位置，按行优先排列
+    pub fn slot_origin(&self, index: usize, layout: &PageLayout) -> (u32, u32) {
+        let col = index as u32 % self.columns;
+        let row = index as u32 / self.columns;
+        (
+            self.origin_x + col * (layout.trim_width + self.gutter_x),
+            self.origin_y + row * (layout.trim_height + self.gutter_y),
+        )
+    }
+}
+
+/// 按成品版面计算拼版方案
+///
+/// 水平方向上成品间距为整数个光栅，各成品的光栅相位在整张纸上连续，
+/// 可以整张覆合同一块光栅板。
+///
+/// 单张成品的 DPI 按成品宽度微调，印出的光栅节距与 1/lpi 略有偏差，
+/// 在整张纸上会累积成数个光栅的错位；拼版因此按 lpi × 光栅像素宽度 的分辨率排版，
+/// 成品的实际宽度为光栅数 / lpi。
+pub fn plan_imposition(layout: &PageLayout, options: &ImpositionOptions) -> Result<ImpositionPlan> {
+    let lenticular_width: u32 = layout.lenticular_widths.iter().sum();
+    if lenticular_width == 0 {
+        return Err(Error::InvalidInput(
+            "成品缺少光栅宽度信息，无法拼版".to_string(),
+        ));
+    }
+    if options.lpi <= 0.0 || options.lpi.is_nan() {
+        return Err(Error::InvalidInput("光栅线数必须大于 0".to_string()));
+    }
+    if options.gutter.to_mm() < 0.0 || options.margin.to_mm() < 0.0 {
+        return Err(Error::InvalidInput(
+            "拼版间距与纸张边距不能为负".to_string(),
+        ));
+    }
+    let dpi = options.lpi * lenticular_width as f64;
+
+    let px = |length: Length| length.to_px(dpi).round().max(0.0) as u32;
+    let sheet_width = px(options.sheet_width);
+    let sheet_height = px(options.sheet_height);
+    let margin = px(options.margin);
+    let gutter_x = options.gutter.to_px(dpi).max(0.0) / lenticular_width as f64;
+    let gutter_x = gutter_x.ceil() as u32 * lenticular_width;
+    let gutter_y = px(options.gutter);
+
+    let available_w = sheet_width.saturating_sub(margin * 2);
+    let available_h = sheet_height.saturating_sub(margin * 2);
+    let columns = (available_w + gutter_x) / (layout.trim_width + gutter_x);
+    let rows = (available_h + gutter_y) / (layout.trim_height + gutter_y);
+    if columns == 0 || rows == 0 {
+        return Err(Error::InvalidInput(format!(
+            "纸张尺寸 {}x{} 像素放不下成品 {}x{} 像素",
+            sheet_width, sheet_height, layout.trim_width, layout.trim_height
+        )));
+    }
+
+    let capacity = (columns * rows) as usize;
+    let copies = options.copies.unwrap_or(capacity).min(capacity);
+    if options.copies.is_some_and(|copies| copies > capacity) {
+        warn!("拼版数量超出纸张容量，仅放置 {} 个", capacity);
+    }
+
+    // 成品区域居中
+    let grid_w = columns * layout.trim_width + (columns - 1) * gutter_x;
+    let grid_h = rows * layout.trim_height + (rows - 1) * gutter_y;
+    let plan = ImpositionPlan {
+        dpi,
+        sheet_width,
+        sheet_height,
+        columns,
+        rows,
+        origin_x: (sheet_width - grid_w) / 2,
+        origin_y: (sheet_height - grid_h) / 2,
+        gutter_x,
+        gutter_y,
+        copies,
+    };
+    debug!("imposition plan: {:?}", plan);
+
+    Ok(plan)
+}
+
+/// 将一个或多个光栅合成图像拼到整张纸上，多个图像时按顺序循环放置
+///
+/// 所有图像的版面（成品尺寸、出血、光栅宽度）与 DPI 必须一致。
+/// 图像数据按像素原样复制，不做任何缩放；相邻成品之间的出血各占间距的一半。
+/// 输出图像的 DPI 见 [`plan_imposition`]。
+pub fn impose(
+    cards: &[MatrixImage<Cmyk8Color>],
+    options: &ImpositionOptions,
+) -> Result<MatrixImage<Cmyk8Color>> {
+    let Some(first) = cards.first() else {
+        return Err(Error::InvalidInput("拼版图像不可为空".to_string()));
+    };
+    let (Some(layout), Some(info)) = (first.layout(), first.info()) else {
+        return Err(Error::InvalidInput(
+            "拼版图像缺少版面或分辨率信息".to_string(),
+        ));
+    };
+    for (index, card) in cards.iter().enumerate() {
+        let matching = card.layout() == Some(layout)
+            && card.info().is_some_and(|i| i.dpi_w == info.dpi_w)
+            && card.width() == layout.bleed_width()
+            && card.height() == layout.bleed_height();
+        if !matching {
+            return Err(Error::InvalidInput(format!(
+                "拼版图像 {:02} 的版面与第一个图像不一致",
+                index
+            )));
+        }
+    }
+
+    let plan = plan_imposition(layout, options)?;
+    debug!(
+        "imposition dpi: {:.3} (card {:.3}), card width {:.2}mm",
+        plan.dpi,
+        info.dpi_w,
+        Length::from_px(layout.trim_width as f64, plan.dpi).to_mm()
+    );
+    let mut sheet: MatrixImage<Cmyk8Color> = MatrixImage::new(plan.sheet_width, plan.sheet_height);
+
+    for index in 0..plan.copies {
+        let card = &cards[index % cards.len()];
+        let (x, y) = plan.slot_origin(index, layout);
+        let (col, row) = (index as u32 % plan.columns, index as u32 / plan.columns);
+
+        // 出血向外延伸的范围：外侧不超出纸张，内侧不超过间距的一半
+        let left = if col == 0 { x } else { plan.gutter_x / 2 };
+        let right = if col == plan.columns - 1 {
+            plan.sheet_width - x - layout.trim_width
+        } else {
+            plan.gutter_x - plan.gutter_x / 2
+        };
+        let top = if row == 0 { y } else { plan.gutter_y / 2 };
+        let bottom = if row == plan.rows - 1 {
+            plan.sheet_height - y - layout.trim_height
+        } else {
+            plan.gutter_y - plan.gutter_y / 2
+        };
+        let (left, right) = (left.min(layout.bleed_x), right.min(layout.bleed_x));
+        let (top, bottom) = (top.min(layout.bleed_y), bottom.min(layout.bleed_y));
+
+        let src_x = (layout.bleed_x - left) as usize;
+        let src_y = (layout.bleed_y - top) as usize;
+        let w = (left + layout.trim_width + right) as usize;
+        let h = (top + layout.trim_height + bottom) as usize;
+        let dst_x = (x - left) as usize;
+        let dst_y = (y - top) as usize;
+        sheet
+            .inner_mut()
+            .slice_mut(s![dst_y..dst_y + h, dst_x..dst_x + w])
+            .assign(&card.inner().slice(s![src_y..src_y + h, src_x..src_x + w]));
+    }
+
+    if options.crop_marks {
+        draw_sheet_crop_marks(&mut sheet, &plan, layout, CropMarks::with_dpi(plan.dpi));
+    }
+
+    sheet.set_info(DpiInfo {
+        dpi_w: plan.dpi,
+        dpi_h: plan.dpi,
+    });
+    sheet.set_icc_profile(first.icc_profile().map(|p| p.to_vec()));
+    sheet.set_lzw(first.lzw());
+    Ok(sheet)
+}
+
+/// 在成品区域外围、对齐每个成品边缘绘制裁切标记
+fn draw_sheet_crop_marks(
+    sheet: &mut MatrixImage<Cmyk8Color>,
+    plan: &ImpositionPlan,
+    layout: &PageLayout,
+    marks: CropMarks,
+) {
+    if plan.copies == 0 {
+        return;
+    }
+    let used_columns = plan.columns.min(plan.copies as u32);
+    let used_rows = (plan.copies as u32).div_ceil(plan.columns);
+    let grid_right =
+        plan.origin_x + used_columns * layout.trim_width + (used_columns - 1) * plan.gutter_x;
+    let grid_bottom =
+        plan.origin_y + used_rows * layout.trim_height + (used_rows - 1) * plan.gutter_y;
+
+    // 标记从出血外侧开始
+    let gap_x = layout.bleed_x + marks.offset;
+    let gap_y = layout.bleed_y + marks.offset;
+    let (width, height) = (plan.sheet_width, plan.sheet_height);
+    if plan.origin_y < gap_y + marks.length || plan.origin_x < gap_x + marks.length {
+        warn!("纸张边距不足，裁切标记将被截断");
+    }
+
+    let top =
+        plan.origin_y.saturating_sub(gap_y + marks.length)..plan.origin_y.saturating_sub(gap_y);
+    let bottom =
+        (grid_bottom + gap_y).min(height)..(grid_bottom + gap_y + marks.length).min(height);
+    let left =
+        plan.origin_x.saturating_sub(gap_x + marks.length)..plan.origin_x.saturating_sub(gap_x);
+    let right = (grid_right + gap_x).min(width)..(grid_right + gap_x + marks.length).min(width);
+
+    let thickness = marks.thickness;
+    let line = |pos: u32, limit: u32| {
+        let start = pos.saturating_sub(thickness / 2);
+        start as usize..(start + thickness).min(limit) as usize
+    };
+    let range = |r: std::ops::Range<u32>| r.start as usize..r.end as usize;
+
+    let mat = sheet.inner_mut();
+    for col in 0..used_columns {
+        let x = plan.origin_x + col * (layout.trim_width + plan.gutter_x);
+        for edge in [x, x + layout.trim_width] {
+            for rows in [top.clone(), bottom.clone()] {
+                mat.slice_mut(s![range(rows), line(edge, width)])
+                    .fill(REGISTRATION_BLACK);
+            }
+        }
+    }
+    for row in 0..used_rows {
+        let y = plan.origin_y + row * (layout.trim_height + plan.gutter_y);
+        for edge in [y, y + layout.trim_height] {
+            for cols in [left.clone(), right.clone()] {
+                mat.slice_mut(s![line(edge, height), range(cols)])
+                    .fill(REGISTRATION_BLACK);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impose_keeps_lens_phase() {
+        let layout = PageLayout {
+            trim_width: 10,
+            trim_height: 6,
+            bleed_x: 2,
+            bleed_y: 1,
+            lenticular_widths: vec![1, 1],
+            ..Default::default()
+        };
+        // 两帧交替：偶数列 k=10，奇数列 k=20
+        let mut card: MatrixImage<Cmyk8Color> =
+            MatrixImage::new(layout.bleed_width(), layout.bleed_height());
+        card.inner_mut().indexed_iter_mut().for_each(|((_, x), c)| {
+            c.k = if x % 2 == 0 { 10 } else { 20 };
+        });
+        card.set_info(DpiInfo {
+            dpi_h: 100.0,
+            dpi_w: 100.0,
+        });
+        card.set_layout(Some(layout.clone()));
+
+        let options = ImpositionOptions {
+            lpi: 50.0,
+            sheet_width: Length::inches(1.0),
+            sheet_height: Length::inches(0.5),
+            gutter: Length::inches(0.05),
+            margin: Length::default(),
+            copies: None,
+            crop_marks: false,
+        };
+        let plan = plan_imposition(&layout, &options).unwrap();
+        assert_eq!(plan.gutter_x, 6);
+        assert_eq!((plan.columns, plan.rows), (6, 5));
+        // 负的间距或边距报错
+        let negative = ImpositionOptions {
+            gutter: Length::mm(-1.0),
+            ..options.clone()
+        };
+        assert!(plan_imposition(&layout, &negative).is_err());
+
+        let sheet = impose(&[card], &options).unwrap();
+        assert_eq!((sheet.width(), sheet.height()), (100, 50));
+        // 每个成品内的光栅相位与第一个成品一致
+        for index in 0..plan.copies {
+            let (x, y) = plan.slot_origin(index, &layout);
+            for dx in 0..layout.trim_width {
+                let expected = if dx % 2 == 0 { 10 } else { 20 };
+                assert_eq!(sheet.inner()[[y as usize, (x + dx) as usize]].k, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_impose_lens_pitch_across_sheet() {
+        // 10.6cm 宽的卡片按 91.6 LPI 合成：382 个光栅，单张 DPI 略低于 lpi × 2
+        let lpi = 91.6;
+        let card_width = Length::cm(10.6);
+        let count = (card_width.to_inches() * lpi).floor() as u32;
+        let layout = PageLayout {
+            trim_width: count * 2,
+            trim_height: 100,
+            lenticular_widths: vec![1, 1],
+            ..Default::default()
+        };
+        let card_dpi = layout.trim_width as f64 / card_width.to_inches();
+        let mut card: MatrixImage<Cmyk8Color> = MatrixImage::new(layout.trim_width, 100);
+        card.set_info(DpiInfo {
+            dpi_w: card_dpi,
+            dpi_h: card_dpi,
+        });
+        card.set_layout(Some(layout.clone()));
+
+        let options = ImpositionOptions {
+            lpi,
+            sheet_width: Length::cm(70.0),
+            sheet_height: Length::cm(10.0),
+            gutter: Length::mm(2.0),
+            margin: Length::default(),
+            copies: None,
+            crop_marks: false,
+        };
+        let sheet = impose(&[card], &options).unwrap();
+        let plan = plan_imposition(&layout, &options).unwrap();
+        let dpi = sheet.info().unwrap().dpi_w;
+        assert_eq!(dpi, plan.dpi);
+        assert!(plan.columns >= 6);
+
+        // 打印后每个光栅恰好 1/lpi 英寸，最后一个成品的右边缘仍落在光栅边界上
+        let pitch = Length::from_px(2.0, dpi);
+        assert!((pitch.to_inches() - 1.0 / lpi).abs() < 1e-12);
+        let (x, _) = plan.slot_origin(plan.columns as usize - 1, &layout);
+        let lenses = Length::from_px((x + layout.trim_width) as f64, dpi).to_inches() * lpi;
+        assert!((lenses - lenses.round()).abs() < 1e-6, "lenses: {lenses}");
+        // 按单张 DPI 排版时，整张纸上的错位超过一个光栅
+        let drift = (x + layout.trim_width) as f64 / card_dpi * lpi;
+        assert!((drift - lenses).abs() > 1.0, "drift: {}", drift - lenses);
+    }
+}
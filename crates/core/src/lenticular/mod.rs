@@ -1,29 +1,95 @@
+mod cache;
+mod calibration;
+mod deinterlace;
+mod diecut;
+mod finishing;
+mod imposition;
+mod pdf;
+#[cfg(feature = "image")]
+mod png;
+mod screening;
+#[cfg(test)]
+pub(crate) mod test_util;
 pub(crate) mod tiff;
+mod tiling;
+mod white;
 
-use std::io::{Read, Seek};
-
+pub use cache::FittedFrameCache;
+pub use calibration::*;
+pub use deinterlace::*;
+pub use diecut::*;
+pub use finishing::*;
+pub use imposition::*;
+pub use pdf::*;
+#[cfg(feature = "image")]
+pub use png::*;
+pub use screening::*;
 pub use tiff::*;
+pub use tiling::*;
+pub use white::*;
+
+use std::sync::Arc;
 
 use crate::{
+    decoder::FrameDecoder,
     error::Result,
-    image::{Cmyk8Color, MatrixImage},
+    image::{Cmyk16Color, Cmyk8Color, CmykSeparation, MatrixImage, Rgb16Color, Rgb8Color},
+    units::Length,
 };
 
 /// 全局选项
 pub struct ProcessOptions {
     pub(crate) lpi: f64,
-    pub(crate) physical_width_cm: f64,
+    pub(crate) physical_width: Option<Length>,
+    pub(crate) physical_height: Option<Length>,
+    pub(crate) fit_mode: FitMode,
     pub(crate) scale_algorithm: Option<ScaleAlgorithm>,
     pub(crate) lzw: bool,
+    pub(crate) icc_profile: Option<Vec<u8>>,
+    pub(crate) cmyk_separation: Option<CmykSeparation>,
+    pub(crate) bleed: Option<Length>,
+    pub(crate) safe_margin: Option<Length>,
+    pub(crate) crop_marks: bool,
+    pub(crate) registration_bars: bool,
+    pub(crate) reverse_print: bool,
+    pub(crate) frame_cache: Option<Arc<FittedFrameCache>>,
 }
 
 impl ProcessOptions {
-    pub fn new(lpi: f64, physical_width_cm: f64) -> Self {
+    pub fn new(lpi: f64, physical_width: Length) -> Self {
+        Self::with_size(lpi, Some(physical_width), None)
+    }
+
+    /// 只指定物理高度，宽度按第一张图的宽高比计算
+    pub fn from_height(lpi: f64, physical_height: Length) -> Self {
+        Self::with_size(lpi, None, Some(physical_height))
+    }
+
+    /// 同时指定物理宽度与高度（如卡片尺寸），宽高比与输入图像不一致时按适配方式处理
+    pub fn from_dimensions(lpi: f64, physical_width: Length, physical_height: Length) -> Self {
+        Self::with_size(lpi, Some(physical_width), Some(physical_height))
+    }
+
+    fn with_size(
+        lpi: f64,
+        physical_width: Option<Length>,
+        physical_height: Option<Length>,
+    ) -> Self {
         Self {
             lpi,
-            physical_width_cm,
+            physical_width,
+            physical_height,
+            fit_mode: FitMode::default(),
             scale_algorithm: None,
             lzw: false,
+            icc_profile: None,
+            cmyk_separation: None,
+            bleed: None,
+            safe_margin: None,
+            crop_marks: false,
+            registration_bars: false,
+            reverse_print: false,
+            frame_cache: None,
         }
     }
 
@@ -32,33 +98,134 @@ impl ProcessOptions {
         self
     }
 
+    /// 固定输出图像的物理高度，不设置时按第一张图的宽高比计算
+    pub fn with_physical_height(mut self, physical_height: Length) -> Self {
+        self.physical_height = Some(physical_height);
+        self
+    }
+
+    /// 输入图像与输出画幅宽高比不一致时的适配方式
+    pub fn with_fit_mode(mut self, fit_mode: FitMode) -> Self {
+        self.fit_mode = fit_mode;
+        self
+    }
+
+    /// 写出 TIFF 时使用 LZW 压缩
     pub fn with_lzw(mut self, lzw: bool) -> Self {
         self.lzw = lzw;
         self
     }
 
-    pub fn calc_output_info<R>(&self, inputs: &mut [InputImageContext<R>]) -> Result<OutputInfo>
+    /// RGB 输入时先分色为 CMYK 8位再进行光栅合成，CMYK 输入不受影响
+    pub fn with_cmyk_separation(mut self, separation: CmykSeparation) -> Self {
+        self.cmyk_separation = Some(separation);
+        self
+    }
+
+    /// 使用指定的 ICC 配置文件替代输入图像中的配置文件
+    ///
+    /// 计算输出信息时检查配置文件头，颜色空间须与输出颜色类型一致。
+    pub fn with_icc_profile(mut self, icc_profile: Vec<u8>) -> Self {
+        self.icc_profile = Some(icc_profile);
+        self
+    }
+
+    /// 出血宽度，水平方向向上取整到整数个光栅
+    ///
+    /// 各帧适配到成品尺寸后复制边缘像素填充出血，成品区域内的画面与不加出血时一致。
+    pub fn with_bleed(mut self, bleed: Length) -> Self {
+        self.bleed = Some(bleed);
+        self
+    }
+
+    /// 安全边距，写出 CMYK 8位图像时绘制参考线，仅用于打样检查
+    pub fn with_safe_margin(mut self, safe_margin: Length) -> Self {
+        self.safe_margin = Some(safe_margin);
+        self
+    }
+
+    /// 写出 CMYK 8位图像时在出血以外绘制裁切标记（套准黑）
+    pub fn with_crop_marks(mut self, crop_marks: bool) -> Self {
+        self.crop_marks = crop_marks;
+        self
+    }
+
+    /// 写出 CMYK 8位图像时在成品上下两侧绘制光栅对位条
+    pub fn with_registration_bars(mut self, registration_bars: bool) -> Self {
+        self.registration_bars = registration_bars;
+        self
+    }
+
+    /// 印刷在光栅板背面（平面一侧）：各帧水平镜像，同时反转光栅内的帧顺序
+    pub fn with_reverse_print(mut self, reverse_print: bool) -> Self {
+        self.reverse_print = reverse_print;
+        self
+    }
+
+    /// 指定了缓存键的输入帧从该缓存中复用裁切、缩放结果
+    pub fn with_frame_cache(mut self, frame_cache: Arc<FittedFrameCache>) -> Self {
+        self.frame_cache = Some(frame_cache);
+        self
+    }
+
+    pub fn calc_output_info<D>(&self, inputs: &mut [InputImageContext<D>]) -> Result<OutputInfo>
     where
-        R: Read + Seek,
+        D: FrameDecoder,
     {
         calc_output_info(inputs, self)
     }
 
-    pub fn process_tiff_cmyk8<R>(
+    pub fn process_tiff_cmyk8<D>(
         &self,
-        inputs: Vec<InputImageContext<R>>,
+        inputs: Vec<InputImageContext<D>>,
         output_info: &OutputInfo,
         resize_alg: ScaleAlgorithm,
     ) -> Result<MatrixImage<Cmyk8Color>>
     where
-        R: Read + Seek,
+        D: FrameDecoder,
+    {
+        process_tiff_cached(inputs, output_info, resize_alg, self.frame_cache.as_deref())
+    }
+
+    pub fn process_tiff_cmyk16<D>(
+        &self,
+        inputs: Vec<InputImageContext<D>>,
+        output_info: &OutputInfo,
+        resize_alg: ScaleAlgorithm,
+    ) -> Result<MatrixImage<Cmyk16Color>>
+    where
+        D: FrameDecoder,
+    {
+        process_tiff_cached(inputs, output_info, resize_alg, self.frame_cache.as_deref())
+    }
+
+    pub fn process_tiff_rgb8<D>(
+        &self,
+        inputs: Vec<InputImageContext<D>>,
+        output_info: &OutputInfo,
+        resize_alg: ScaleAlgorithm,
+    ) -> Result<MatrixImage<Rgb8Color>>
+    where
+        D: FrameDecoder,
     {
-        process_tiff_cmyk8(inputs, output_info, resize_alg)
+        process_tiff_cached(inputs, output_info, resize_alg, self.frame_cache.as_deref())
+    }
+
+    pub fn process_tiff_rgb16<D>(
+        &self,
+        inputs: Vec<InputImageContext<D>>,
+        output_info: &OutputInfo,
+        resize_alg: ScaleAlgorithm,
+    ) -> Result<MatrixImage<Rgb16Color>>
+    where
+        D: FrameDecoder,
+    {
+        process_tiff_cached(inputs, output_info, resize_alg, self.frame_cache.as_deref())
     }
 }
 
 /// 缩放算法
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub enum ScaleAlgorithm {
     Nearest,
     #[default]
@@ -80,6 +247,54 @@ impl From<ScaleAlgorithm> for fast_image_resize::ResizeAlg {
     }
 }
 
+/// 输入图像与输出画幅宽高比不一致时的适配方式
+///
+/// 除 `Stretch` 外，各输入图像的尺寸可以不同。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FitMode {
+    /// 拉伸到输出尺寸，所有输入图像的尺寸必须一致
+    #[default]
+    Stretch,
+    /// 等比缩放填满输出画幅，超出部分按锚点裁切
+    Fill { anchor: Anchor },
+    /// 等比缩放完整显示并居中，空白部分填充背景色
+    ///
+    /// CMYK 输出时背景色按分色参数转换（未设置时使用默认分色参数）。
+    Fit { background: Rgb8Color },
+}
+
+/// 裁切锚点
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// 锚点在水平、垂直方向上的相对位置（0~1）
+    pub fn factors(&self) -> (f64, f64) {
+        match self {
+            Anchor::TopLeft => (0.0, 0.0),
+            Anchor::Top => (0.5, 0.0),
+            Anchor::TopRight => (1.0, 0.0),
+            Anchor::Left => (0.0, 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1.0, 0.5),
+            Anchor::BottomLeft => (0.0, 1.0),
+            Anchor::Bottom => (0.5, 1.0),
+            Anchor::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
 /// 针对每张图的选项
 #[derive(Debug, Clone)]
 pub struct ImageOptions {
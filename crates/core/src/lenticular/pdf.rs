@@ -0,0 +1,372 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{write::ZlibEncoder, Compression};
+
+use crate::{
+    error::{Error, Result},
+    image::{Cmyk8Color, MatrixImage},
+    units::Length,
+};
+
+use super::{apply_print_marks, check_icc_profile};
+
+/// PDF 输出选项
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    /// 使用 Flate 无损压缩图像数据
+    pub compress: bool,
+    /// 输出意图的印刷条件标识
+    pub output_condition: String,
+    /// 印刷条件的说明，标识为 `Custom` 等未注册的条件时写入输出意图的 `/Info`
+    pub output_condition_info: String,
+    /// 文档标题
+    pub title: String,
+    /// 文档的创建与修改时间
+    pub creation_time: SystemTime,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            compress: true,
+            output_condition: "Custom".to_string(),
+            output_condition_info: "Custom CMYK".to_string(),
+            title: "Lenticular image".to_string(),
+            creation_time: SystemTime::now(),
+        }
+    }
+}
+
+/// 写出 CMYK 8位 PDF，页面尺寸与图像的物理尺寸一致
+///
+/// 图像按原始像素嵌入（不重新采样），每个像素对应页面上 1/DPI 英寸。
+/// 图像带有版面信息时，TrimBox/BleedBox 按成品与出血设置，并与 TIFF 输出一样绘制标记；
+/// 带有 ICC 配置文件时作为输出意图嵌入，并按 PDF/X-3:2002（PDF 1.3）标记文档；
+/// 配置文件的颜色空间须为 CMYK。
+pub fn write_pdf_cmyk8<W>(
+    mut writer: W,
+    out: &MatrixImage<Cmyk8Color>,
+    options: &PdfOptions,
+) -> Result<()>
+where
+    W: Write,
+{
+    let Some(info) = out.info() else {
+        return Err(Error::InvalidInput(
+            "图像缺少分辨率信息，无法确定 PDF 页面尺寸".to_string(),
+        ));
+    };
+    let marked;
+    let (img, layout) = match out.layout() {
+        Some(layout) if layout.has_marks() => {
+            marked = apply_print_marks(out, layout);
+            (&marked, Some(layout))
+        }
+        layout => (out, layout),
+    };
+
+    let pt_x = |px: u32| Length::from_px(px as f64, info.dpi_w).to_pt();
+    let pt_y = |px: u32| Length::from_px(px as f64, info.dpi_h).to_pt();
+    let media_box = [0.0, 0.0, pt_x(img.width()), pt_y(img.height())];
+    // 版面四周对称，按左下角偏移计算
+    let (trim_box, bleed_box) = match layout {
+        Some(layout) => {
+            let slug = if layout.has_marks() { layout.slug() } else { 0 };
+            let bleed = [pt_x(slug), pt_y(slug)];
+            let trim = [pt_x(slug + layout.bleed_x), pt_y(slug + layout.bleed_y)];
+            (
+                [
+                    trim[0],
+                    trim[1],
+                    trim[0] + pt_x(layout.trim_width),
+                    trim[1] + pt_y(layout.trim_height),
+                ],
+                [
+                    bleed[0],
+                    bleed[1],
+                    bleed[0] + pt_x(layout.bleed_width()),
+                    bleed[1] + pt_y(layout.bleed_height()),
+                ],
+            )
+        }
+        None => (media_box, media_box),
+    };
+
+    let mut data = img.to_bytes();
+    if options.compress {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&data)?;
+        data = encoder.finish()?;
+    }
+
+    if let Some(icc_profile) = img.icc_profile() {
+        check_icc_profile(icc_profile, Some(tiff::ColorType::CMYK(8)))?;
+    }
+
+    let mut pdf = PdfBuilder::default();
+    let icc_id = img.icc_profile().map(|_| 6);
+
+    // 1: Catalog
+    let output_intents = match icc_id {
+        Some(id) => format!(
+            " /OutputIntents [<< /Type /OutputIntent /S /GTS_PDFX /OutputConditionIdentifier {} /Info {} /DestOutputProfile {} 0 R >>]",
+            pdf_string(&options.output_condition),
+            pdf_string(&options.output_condition_info),
+            id
+        ),
+        None => String::new(),
+    };
+    pdf.object(
+        1,
+        format!("<< /Type /Catalog /Pages 2 0 R{} >>", output_intents).as_bytes(),
+    );
+    // 2: Pages
+    pdf.object(2, b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>");
+    // 3: Page
+    pdf.object(
+        3,
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox {} /BleedBox {} /TrimBox {} /Resources << /XObject << /Im0 5 0 R >> >> /Contents 4 0 R >>",
+            pdf_rect(media_box),
+            pdf_rect(bleed_box),
+            pdf_rect(trim_box)
+        )
+        .as_bytes(),
+    );
+    // 4: 内容流，将图像铺满页面
+    let content = format!(
+        "q {:.4} 0 0 {:.4} 0 0 cm /Im0 Do Q",
+        media_box[2], media_box[3]
+    );
+    pdf.stream(4, "", content.as_bytes());
+    // 5: 图像
+    pdf.stream(
+        5,
+        &format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceCMYK /BitsPerComponent 8 /Interpolate false{}",
+            img.width(),
+            img.height(),
+            if options.compress { " /Filter /FlateDecode" } else { "" }
+        ),
+        &data,
+    );
+    // 6: 输出意图 ICC 配置文件
+    if let (Some(id), Some(icc_profile)) = (icc_id, img.icc_profile()) {
+        pdf.stream(id, "/N 4", icc_profile);
+    }
+    // 7: 文档信息
+    let date = pdf_string(&pdf_date(options.creation_time));
+    let mut info_dict = format!(
+        "<< /Title {} /Producer {} /CreationDate {} /ModDate {} /Trapped /False",
+        pdf_text_string(&options.title),
+        pdf_string(concat!(
+            "lenticular-image-tool",
+            " ",
+            env!("CARGO_PKG_VERSION")
+        )),
+        date,
+        date
+    );
+    if icc_id.is_some() {
+        info_dict.push_str(" /GTS_PDFXVersion (PDF/X-3:2002)");
+    }
+    info_dict.push_str(" >>");
+    pdf.object(7, info_dict.as_bytes());
+
+    writer.write_all(&pdf.finish(1, 7))?;
+    Ok(())
+}
+
+/// 按对象编号顺序拼接 PDF 并生成交叉引用表
+#[derive(Default)]
+struct PdfBuilder {
+    buf: Vec<u8>,
+    offsets: Vec<(u32, usize)>,
+}
+
+impl PdfBuilder {
+    fn header(&mut self) {
+        if self.buf.is_empty() {
+            // PDF/X-3:2002 要求 PDF 1.3；第二行的二进制注释提示传输工具按二进制处理
+            self.buf.extend_from_slice(b"%PDF-1.3\n%\xE2\xE3\xCF\xD3\n");
+        }
+    }
+
+    fn object(&mut self, id: u32, body: &[u8]) {
+        self.header();
+        self.offsets.push((id, self.buf.len()));
+        self.buf
+            .extend_from_slice(format!("{} 0 obj\n", id).as_bytes());
+        self.buf.extend_from_slice(body);
+        self.buf.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, id: u32, dict: &str, data: &[u8]) {
+        let mut body = format!("<< {} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        self.object(id, &body);
+    }
+
+    fn finish(mut self, root: u32, info: u32) -> Vec<u8> {
+        self.offsets.sort();
+        let size = self.offsets.last().map(|(id, _)| id + 1).unwrap_or(1);
+
+        let mut hasher = DefaultHasher::new();
+        self.buf.hash(&mut hasher);
+        let id = format!("{:016x}{:016x}", hasher.finish(), self.buf.len());
+
+        let xref_offset = self.buf.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", size);
+        let mut offsets = self.offsets.iter().peekable();
+        for obj in 1..size {
+            match offsets.next_if(|(id, _)| *id == obj) {
+                Some((_, offset)) => xref.push_str(&format!("{:010} 00000 n \n", offset)),
+                None => xref.push_str("0000000000 65535 f \n"),
+            }
+        }
+        xref.push_str(&format!(
+            "trailer\n<< /Size {} /Root {} 0 R /Info {} 0 R /ID [<{}> <{}>] >>\nstartxref\n{}\n%%EOF\n",
+            size, root, info, id, id, xref_offset
+        ));
+        self.buf.extend_from_slice(xref.as_bytes());
+        self.buf
+    }
+}
+
+fn pdf_rect(rect: [f64; 4]) -> String {
+    format!(
+        "[{:.4} {:.4} {:.4} {:.4}]",
+        rect[0], rect[1], rect[2], rect[3]
+    )
+}
+
+/// PDF 日期字符串（UTC），格式：D:YYYYMMDDHHmmSS+00'00'
+fn pdf_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, secs) = ((secs / 86400) as i64, secs % 86400);
+    // 由 1970-01-01 起的天数换算公历日期
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "D:{:04}{:02}{:02}{:02}{:02}{:02}+00'00'",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// PDF 文本字符串，含非 ASCII 字符时按 UTF-16BE 编码
+fn pdf_text_string(value: &str) -> String {
+    if value.is_ascii() {
+        return pdf_string(value);
+    }
+    let hex = [0xfeff]
+        .into_iter()
+        .chain(value.encode_utf16())
+        .map(|unit| format!("{:04X}", unit))
+        .collect::<String>();
+    format!("<{}>", hex)
+}
+
+/// PDF 字面字符串，转义括号与反斜杠
+fn pdf_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)");
+    format!("({})", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::ZlibDecoder;
+
+    use crate::{
+        image::DpiInfo,
+        lenticular::{test_util::icc_profile, PageLayout},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_write_pdf_cmyk8() {
+        let layout = PageLayout {
+            trim_width: 60,
+            trim_height: 30,
+            bleed_x: 6,
+            bleed_y: 6,
+            ..Default::default()
+        };
+        let mut img: MatrixImage<Cmyk8Color> =
+            MatrixImage::new(layout.bleed_width(), layout.bleed_height());
+        img.inner_mut()[[1, 2]].k = 200;
+        img.set_info(DpiInfo {
+            dpi_h: 72.0,
+            dpi_w: 72.0,
+        });
+        img.set_layout(Some(layout));
+        img.set_icc_profile(Some(icc_profile(b"CMYK")));
+
+        let options = PdfOptions {
+            title: "光栅".to_string(),
+            creation_time: UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000),
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        write_pdf_cmyk8(&mut buf, &img, &options).unwrap();
+        let text = String::from_utf8_lossy(&buf);
+        assert!(text.starts_with("%PDF-1.3"));
+        // 72 DPI 时像素与点一一对应
+        assert!(text.contains("/MediaBox [0.0000 0.0000 72.0000 42.0000]"));
+        assert!(text.contains("/TrimBox [6.0000 6.0000 66.0000 36.0000]"));
+        assert!(text.contains("/DestOutputProfile 6 0 R"));
+        // PDF/X-3 要求的文档信息与未注册印刷条件的说明
+        assert!(text.contains("/OutputConditionIdentifier (Custom) /Info (Custom CMYK)"));
+        assert!(text.contains("/Title <FEFF51496805>"));
+        assert!(text.contains("/CreationDate (D:20231114221320+00'00')"));
+        assert!(text.contains("/ModDate (D:20231114221320+00'00')"));
+        assert!(text.contains("/GTS_PDFXVersion (PDF/X-3:2002)"));
+
+        // 图像数据无损还原
+        let find = |from: usize, needle: &[u8]| {
+            buf[from..]
+                .windows(needle.len())
+                .position(|w| w == needle)
+                .unwrap()
+                + from
+        };
+        let stream_start = find(find(0, b"/Subtype /Image"), b"stream\n") + 7;
+        let stream_end = find(stream_start, b"\nendstream");
+        let mut decoded = vec![];
+        ZlibDecoder::new(&buf[stream_start..stream_end])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, img.to_bytes());
+
+        // 输出意图的配置文件须为 CMYK
+        let mut icc_profile = img.icc_profile().unwrap().to_vec();
+        icc_profile[16..20].copy_from_slice(b"RGB ");
+        img.set_icc_profile(Some(icc_profile));
+        assert!(write_pdf_cmyk8(&mut vec![], &img, &options).is_err());
+    }
+}
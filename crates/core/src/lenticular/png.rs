@@ -0,0 +1,23 @@
+use std::io::Write;
+
+use image::{codecs::png::PngEncoder, ExtendedColorType, ImageEncoder};
+
+use crate::{
+    error::Result,
+    image::{MatrixImage, Rgb8Color},
+};
+
+pub fn write_png_rgb8<W>(writer: W, out: &MatrixImage<Rgb8Color>) -> Result<()>
+where
+    W: Write,
+{
+    let encoder = PngEncoder::new(writer);
+    encoder.write_image(
+        &out.to_bytes(),
+        out.width(),
+        out.height(),
+        ExtendedColorType::Rgb8,
+    )?;
+
+    Ok(())
+}
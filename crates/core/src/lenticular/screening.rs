@@ -0,0 +1,333 @@
+use std::f64::consts::PI;
+
+use log::debug;
+
+use crate::{
+    error::{Error, Result},
+    image::{Cmyk8Color, MatrixImage},
+};
+
+use super::{apply_print_marks, column_frame_indices};
+
+/// 分色版名称，顺序与 CMYK 通道一致
+pub const SEPARATION_NAMES: [&str; 4] = ["C", "M", "Y", "K"];
+
+/// 加网方式
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Screening {
+    /// 调频网：Floyd–Steinberg 误差扩散（蛇形扫描），误差只在同一光栅条内扩散
+    #[default]
+    ErrorDiffusion,
+    /// 调幅网：圆形网点，`angles` 依次为 C/M/Y/K 的网角（度）
+    Amplitude { frequency: f64, angles: [f64; 4] },
+}
+
+/// 加网选项
+#[derive(Debug, Clone, Default)]
+pub struct ScreeningOptions {
+    pub screening: Screening,
+    /// 设备分辨率，不设置时使用图像分辨率。放大时按最近邻取样，光栅条边缘保持清晰。
+    pub device_dpi: Option<f64>,
+    /// 调幅网按光栅线数修正各色网线频率，使网点的水平频率为光栅频率的整数倍
+    pub lock_to_lens: bool,
+}
+
+/// 1 位分色版，按行打包，高位在前，1 表示着墨
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    pub width: u32,
+    pub height: u32,
+    pub dpi_w: f64,
+    pub dpi_h: f64,
+    pub data: Vec<u8>,
+}
+
+impl Bitmap {
+    fn new(width: u32, height: u32, dpi_w: f64, dpi_h: f64) -> Self {
+        Self {
+            width,
+            height,
+            dpi_w,
+            dpi_h,
+            data: vec![0; width.div_ceil(8) as usize * height as usize],
+        }
+    }
+
+    /// 每行字节数
+    pub fn row_bytes(&self) -> usize {
+        self.width.div_ceil(8) as usize
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        let byte = self.data[y as usize * self.row_bytes() + x as usize / 8];
+        byte & (0x80 >> (x % 8)) != 0
+    }
+
+    fn set(&mut self, x: usize, y: usize) {
+        let row_bytes = self.row_bytes();
+        self.data[y * row_bytes + x / 8] |= 0x80 >> (x % 8);
+    }
+
+    /// 着墨像素占比
+    pub fn coverage(&self) -> f64 {
+        let inked: u32 = self.data.iter().map(|b| b.count_ones()).sum();
+        inked as f64 / (self.width as f64 * self.height as f64)
+    }
+}
+
+/// 将光栅合成图像加网为 C/M/Y/K 四张 1 位分色版
+///
+/// 图像带有版面信息时与 TIFF 输出一样先绘制标记；误差扩散按版面中的光栅宽度表
+/// 划分光栅条，避免相邻帧之间互相传递误差。
+pub fn screen_cmyk8(
+    img: &MatrixImage<Cmyk8Color>,
+    options: &ScreeningOptions,
+) -> Result<[Bitmap; 4]> {
+    let Some(info) = img.info() else {
+        return Err(Error::InvalidInput(
+            "图像缺少分辨率信息，无法加网".to_string(),
+        ));
+    };
+    let marked;
+    let (src, slug) = match img.layout() {
+        Some(layout) if layout.has_marks() => {
+            marked = apply_print_marks(img, layout);
+            (&marked, layout.slug() as usize)
+        }
+        _ => (img, 0),
+    };
+
+    let device_dpi = options.device_dpi.unwrap_or(info.dpi_w);
+    if device_dpi <= 0.0 {
+        return Err(Error::InvalidInput("设备分辨率必须大于0".to_string()));
+    }
+    let width = (src.width() as f64 * device_dpi / info.dpi_w)
+        .round()
+        .max(1.0) as usize;
+    let height = (src.height() as f64 * device_dpi / info.dpi_h)
+        .round()
+        .max(1.0) as usize;
+    // 设备像素对应的源像素（最近邻）
+    let src_x = (0..width)
+        .map(|x| x * src.width() as usize / width)
+        .collect::<Vec<_>>();
+    let src_y = (0..height)
+        .map(|y| y * src.height() as usize / height)
+        .collect::<Vec<_>>();
+
+    // 每个设备列所属的光栅条，标记区域各自为一条
+    let lenticular_widths = img
+        .layout()
+        .map(|layout| layout.lenticular_widths.clone())
+        .unwrap_or_default();
+    let strips = if lenticular_widths.is_empty() {
+        vec![0; src.width() as usize]
+    } else {
+        let frames = column_frame_indices(img.width(), &lenticular_widths);
+        let mut strips = vec![0; src.width() as usize];
+        let mut strip = 1;
+        for col in 1..frames.len() {
+            if frames[col] != frames[col - 1] {
+                strip += 1;
+            }
+            strips[slug + col] = strip;
+        }
+        strips[slug] = 1;
+        strips[slug + frames.len()..].fill(strip + 1);
+        strips
+    };
+    let device_strips = src_x.iter().map(|&x| strips[x]).collect::<Vec<_>>();
+
+    let mat = src.inner();
+    let sample = |channel: usize, x: usize, y: usize| {
+        let c = mat[[src_y[y], src_x[x]]];
+        [c.c, c.m, c.y, c.k][channel] as f64 / 255.0
+    };
+
+    let lens_lpi = (!lenticular_widths.is_empty())
+        .then(|| info.dpi_w / lenticular_widths.iter().sum::<u32>() as f64);
+    if options.lock_to_lens && lens_lpi.is_none() {
+        return Err(Error::InvalidInput(
+            "图像缺少光栅宽度信息，无法按光栅线数修正网线频率".to_string(),
+        ));
+    }
+
+    let mut plates =
+        [0, 1, 2, 3].map(|_| Bitmap::new(width as u32, height as u32, device_dpi, device_dpi));
+    for (channel, plate) in plates.iter_mut().enumerate() {
+        match &options.screening {
+            Screening::ErrorDiffusion => {
+                error_diffusion(plate, &device_strips, |x, y| sample(channel, x, y))
+            }
+            Screening::Amplitude { frequency, angles } => {
+                let angle = angles[channel];
+                let frequency = match lens_lpi.filter(|_| options.lock_to_lens) {
+                    Some(lens_lpi) => lock_frequency(*frequency, angle, lens_lpi),
+                    None => *frequency,
+                };
+                debug!(
+                    "{}: AM screen {:.2} lpi @ {}°",
+                    SEPARATION_NAMES[channel], frequency, angle
+                );
+                amplitude(plate, device_dpi / frequency, angle, |x, y| {
+                    sample(channel, x, y)
+                })
+            }
+        }
+    }
+
+    Ok(plates)
+}
+
+/// 调整网线频率，使其水平分量为光栅频率的整数倍；接近 90° 的网角不受光栅影响，保持不变
+fn lock_frequency(frequency: f64, angle: f64, lens_lpi: f64) -> f64 {
+    let cos = angle.to_radians().cos().abs();
+    if cos < 1e-3 {
+        return frequency;
+    }
+    let harmonic = (frequency * cos / lens_lpi).round().max(1.0);
+    harmonic * lens_lpi / cos
+}
+
+fn error_diffusion<F>(plate: &mut Bitmap, strips: &[usize], sample: F)
+where
+    F: Fn(usize, usize) -> f64,
+{
+    let (width, height) = (plate.width as usize, plate.height as usize);
+    let mut current = vec![0.0; width];
+    let mut next = vec![0.0; width];
+
+    for y in 0..height {
+        let forward = y % 2 == 0;
+        for i in 0..width {
+            let x = if forward { i } else { width - 1 - i };
+            let value = sample(x, y) + current[x];
+            let inked = value >= 0.5;
+            if inked {
+                plate.set(x, y);
+            }
+            let error = value - if inked { 1.0 } else { 0.0 };
+
+            // 前方与下一行左右两侧只在同一光栅条内时参与扩散
+            let ahead = if forward {
+                x.checked_add(1).filter(|&x| x < width)
+            } else {
+                x.checked_sub(1)
+            };
+            let behind = if forward {
+                x.checked_sub(1)
+            } else {
+                x.checked_add(1).filter(|&x| x < width)
+            };
+            let ahead = ahead.filter(|&n| strips[n] == strips[x]);
+            let behind = behind.filter(|&n| strips[n] == strips[x]);
+
+            let has_next_row = y + 1 < height;
+            let mut total = 0.0;
+            if ahead.is_some() {
+                total += 7.0;
+            }
+            if has_next_row {
+                total += 5.0;
+                if behind.is_some() {
+                    total += 3.0;
+                }
+                if ahead.is_some() {
+                    total += 1.0;
+                }
+            }
+            if total == 0.0 {
+                continue;
+            }
+            let error = error / total;
+            if let Some(n) = ahead {
+                current[n] += error * 7.0;
+            }
+            if has_next_row {
+                next[x] += error * 5.0;
+                if let Some(n) = behind {
+                    next[n] += error * 3.0;
+                }
+                if let Some(n) = ahead {
+                    next[n] += error;
+                }
+            }
+        }
+        std::mem::swap(&mut current, &mut next);
+        next.fill(0.0);
+    }
+}
+
+/// 圆形网点：网点中心阈值最低，按覆盖率向外扩展
+fn amplitude<F>(plate: &mut Bitmap, period: f64, angle: f64, sample: F)
+where
+    F: Fn(usize, usize) -> f64,
+{
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let scale = 2.0 * PI / period;
+    for y in 0..plate.height as usize {
+        for x in 0..plate.width as usize {
+            let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+            let u = (px * cos + py * sin) * scale;
+            let v = (-px * sin + py * cos) * scale;
+            let threshold = 0.5 - (u.cos() + v.cos()) / 4.0;
+            if threshold < sample(x, y) {
+                plate.set(x, y);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{image::DpiInfo, lenticular::PageLayout};
+
+    use super::*;
+
+    #[test]
+    fn test_screen_cmyk8() {
+        // 两帧交替：偶数列 c=0%，奇数列 c=50%
+        let layout = PageLayout {
+            trim_width: 40,
+            trim_height: 20,
+            lenticular_widths: vec![1, 1],
+            ..Default::default()
+        };
+        let mut img: MatrixImage<Cmyk8Color> = MatrixImage::new(40, 20);
+        img.inner_mut().indexed_iter_mut().for_each(|((_, x), c)| {
+            c.c = if x % 2 == 0 { 0 } else { 128 };
+            c.k = 255;
+        });
+        img.set_info(DpiInfo {
+            dpi_h: 100.0,
+            dpi_w: 100.0,
+        });
+        img.set_layout(Some(layout));
+
+        let options = ScreeningOptions {
+            device_dpi: Some(400.0),
+            ..Default::default()
+        };
+        let [c, _, y, k] = screen_cmyk8(&img, &options).unwrap();
+        assert_eq!((c.width, c.height), (160, 80));
+        assert_eq!(y.coverage(), 0.0);
+        assert_eq!(k.coverage(), 1.0);
+        // 误差不跨光栅条扩散：0% 的条内没有网点
+        assert!((0..160).all(|x| (x / 4) % 2 == 1 || (0..80).all(|y| !c.get(x, y))));
+        assert!((c.coverage() - 0.25).abs() < 0.02);
+
+        let options = ScreeningOptions {
+            screening: Screening::Amplitude {
+                frequency: 20.0,
+                angles: [15.0, 75.0, 0.0, 45.0],
+            },
+            device_dpi: Some(400.0),
+            lock_to_lens: true,
+        };
+        let [c, ..] = screen_cmyk8(&img, &options).unwrap();
+        assert!((c.coverage() - 0.25).abs() < 0.05);
+        // 光栅 50 lpi：0° 网角锁定到 50 lpi 的整数倍
+        assert_eq!(lock_frequency(120.0, 0.0, 50.0), 100.0);
+        assert_eq!(lock_frequency(120.0, 90.0, 50.0), 120.0);
+    }
+}
@@ -0,0 +1,86 @@
+//! 测试用的内存帧、TIFF 文件与 ICC 配置文件
+
+use std::io::Cursor;
+
+use tiff::{encoder::Rational, tags::Tag as TiffTag};
+
+use crate::decoder::{FrameData, MemoryFrameDecoder, TiffFrameDecoder};
+
+use super::{ImageOptions, InputImageContext, SourceParams};
+
+/// 指定颜色类型与尺寸的源图像参数
+pub(crate) fn source_params(color_type: tiff::ColorType, width: u32, height: u32) -> SourceParams {
+    let mut params = SourceParams::default();
+    params.set_color_type(color_type);
+    params.set_source_dimensions(width, height);
+    params
+}
+
+/// 已解码到内存中的 8位帧，`data` 按行优先排列
+pub(crate) fn memory_frame(
+    color_type: tiff::ColorType,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+) -> MemoryFrameDecoder {
+    MemoryFrameDecoder::new(
+        source_params(color_type, width, height),
+        FrameData::U8(data),
+    )
+}
+
+/// 同 [`memory_frame`]，每帧在一个光栅内占用 `lenticular_width_px` 列
+pub(crate) fn input_frame(
+    color_type: tiff::ColorType,
+    width: u32,
+    height: u32,
+    data: Vec<u8>,
+    lenticular_width_px: u32,
+) -> InputImageContext<MemoryFrameDecoder> {
+    InputImageContext::new(
+        memory_frame(color_type, width, height, data),
+        ImageOptions {
+            lenticular_width_px,
+        },
+    )
+}
+
+/// 只含文件头的 ICC 配置文件，其后附 8 字节内容
+pub(crate) fn icc_profile(color_space: &[u8; 4]) -> Vec<u8> {
+    let mut icc_profile = vec![0; 136];
+    icc_profile[0..4].copy_from_slice(&136u32.to_be_bytes());
+    icc_profile[12..16].copy_from_slice(b"prtr");
+    icc_profile[16..20].copy_from_slice(color_space);
+    icc_profile[36..40].copy_from_slice(b"acsp");
+    icc_profile
+}
+
+/// 2x2 的 CMYK 8位 TIFF，只写入 `resolution` 中给出的分辨率标签
+pub(crate) fn tiff_with_resolution(
+    resolution: &[(TiffTag, u32)],
+) -> TiffFrameDecoder<Cursor<Vec<u8>>> {
+    let mut buf = Cursor::new(Vec::new());
+    let mut encoder = tiff::encoder::TiffEncoder::new(&mut buf).unwrap();
+    let mut dir = encoder.new_directory().unwrap();
+    let offset = dir.write_data(&[0u8; 2 * 2 * 4][..]).unwrap();
+    dir.write_tag(TiffTag::ImageWidth, 2u32).unwrap();
+    dir.write_tag(TiffTag::ImageLength, 2u32).unwrap();
+    dir.write_tag(TiffTag::BitsPerSample, &[8u16; 4][..])
+        .unwrap();
+    dir.write_tag(TiffTag::Compression, 1u16).unwrap();
+    // 5：CMYK
+    dir.write_tag(TiffTag::PhotometricInterpretation, 5u16)
+        .unwrap();
+    dir.write_tag(TiffTag::StripOffsets, offset as u32).unwrap();
+    dir.write_tag(TiffTag::SamplesPerPixel, 4u16).unwrap();
+    dir.write_tag(TiffTag::RowsPerStrip, 2u32).unwrap();
+    dir.write_tag(TiffTag::StripByteCounts, 16u32).unwrap();
+    for &(tag, value) in resolution {
+        match tag {
+            TiffTag::ResolutionUnit => dir.write_tag(tag, value as u16).unwrap(),
+            _ => dir.write_tag(tag, Rational { n: value, d: 1 }).unwrap(),
+        }
+    }
+    dir.finish().unwrap();
+    TiffFrameDecoder::new(Cursor::new(buf.into_inner()))
+}
@@ -1,38 +1,61 @@
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::{
+    io::{Seek, Write},
+    sync::Arc,
+};
 
 use log::{debug, warn};
-use ndarray::Axis;
+use ndarray::{s, Axis};
 use tiff::{
-    decoder::{ifd::Value as TiffValue, DecodingResult as TiffDecodingResult},
-    encoder::{colortype, compression::Lzw, Rational},
-    tags::Tag as TiffTag,
+    decoder::ifd::Value as TiffValue,
+    encoder::{
+        colortype,
+        compression::{Compression, Lzw, Uncompressed},
+        ImageEncoder, Rational, TiffKind, TiffValue as TiffEncodeValue,
+    },
+    tags::{PhotometricInterpretation, SampleFormat, Tag as TiffTag, Type as TiffType},
 };
 
 use crate::{
+    decoder::{FrameData, FrameDecoder},
     error::{Error, Result},
-    image::{resize_cmyk8, Cmyk8Color, DpiInfo, MatrixImage},
+    image::{
+        resize_cmyk16, resize_cmyk8, resize_gray8, resize_rgb16, resize_rgb8, Cmyk16Color,
+        Cmyk8Color, CmykSeparation, Color, DpiInfo, Gray8Color, MatrixImage, Rgb16Color, Rgb8Color,
+    },
     lenticular::create_line_index_mapping_advanced,
+    units::Length,
 };
 
-use super::{ImageOptions, ProcessOptions, ScaleAlgorithm};
+use super::{
+    apply_print_marks, cache::FitSignature, pad_slug, Bitmap, CropMarks, FitMode, FittedFrameCache,
+    ImageOptions, PageLayout, ProcessOptions, RegistrationBars, ScaleAlgorithm,
+};
 
 /// 带上下文的输入文件
-pub struct InputImageContext<R> {
-    reader: R,
+pub struct InputImageContext<D> {
+    decoder: D,
     image_options: ImageOptions,
+    cache_key: Option<String>,
 }
 
-impl<R> InputImageContext<R>
+impl<D> InputImageContext<D>
 where
-    R: Read + Seek,
+    D: FrameDecoder,
 {
-    pub fn new(reader: R, options: ImageOptions) -> Self {
+    pub fn new(decoder: D, options: ImageOptions) -> Self {
         Self {
-            reader,
+            decoder,
             image_options: options,
+            cache_key: None,
         }
     }
 
+    /// 设置在 [`FittedFrameCache`] 中的键，相同键的帧视为同一图像，适配结果可以复用
+    pub fn with_cache_key(mut self, key: impl Into<String>) -> Self {
+        self.cache_key = Some(key.into());
+        self
+    }
+
     pub fn image_options(&self) -> &ImageOptions {
         &self.image_options
     }
@@ -46,33 +69,63 @@ where
 /// 计算过程所需的参数表
 struct Params {
     lpi: f64,
-    physical_width_cm: f64,
+    physical_width: Option<Length>,
+    physical_height: Option<Length>,
 
     source_params: SourceParams,
 }
 
 impl Params {
-    pub fn new(lpi: f64, physical_width_cm: f64) -> Self {
+    pub fn new(lpi: f64, physical_width: Option<Length>, physical_height: Option<Length>) -> Self {
         Self {
             lpi,
-            physical_width_cm,
+            physical_width,
+            physical_height,
 
             ..Default::default()
         }
     }
 
-    pub fn physical_width_in(&self) -> f64 {
-        self.physical_width_cm * 0.3937
+    /// 原图宽高比
+    pub fn source_ratio(&self) -> f64 {
+        self.source_params.width as f64 / self.source_params.height as f64
+    }
+
+    /// 物理宽度，只指定高度时按原图宽高比计算
+    pub fn physical_width(&self) -> Length {
+        match (self.physical_width, self.physical_height) {
+            (Some(width), _) => width,
+            (None, Some(height)) => height * self.source_ratio(),
+            (None, None) => Length::default(),
+        }
     }
 }
 
 /// 输出图像信息
 #[derive(Debug, Clone)]
 pub struct OutputInfo {
+    /// 光栅合成区域宽度（含出血）
     pub width: u32,
+    /// 光栅合成区域高度（含出血）
     pub height: u32,
     pub dpi_w: f64,
     pub dpi_h: f64,
+    /// 成品物理宽度（不含出血）
+    pub physical_width: Length,
+    /// 成品物理高度（不含出血），由像素高度按 DPI 换算
+    pub physical_height: Length,
+    /// 出血、安全边距与裁切标记版面
+    pub layout: PageLayout,
+    /// 用户指定的 ICC 配置文件，未指定时沿用输入图像中的配置文件
+    pub icc_override: Option<Vec<u8>>,
+    /// RGB 输入的分色参数，设置时输出为 CMYK 8位
+    pub cmyk_separation: Option<CmykSeparation>,
+    /// 写出 TIFF 时使用 LZW 压缩
+    pub lzw: bool,
+    /// 输入图像的适配方式
+    pub fit_mode: FitMode,
+    /// 各帧原图的尺寸，按输入顺序排列
+    pub frame_sizes: Vec<(u32, u32)>,
 
     pub source_params: SourceParams,
 }
@@ -85,6 +138,7 @@ pub struct SourceParams {
     pub resolution_unit: u32,
     pub x_resolution: Option<TiffValue>,
     pub y_resolution: Option<TiffValue>,
+    pub icc_profile: Option<Vec<u8>>,
 }
 
 impl SourceParams {
@@ -107,29 +161,79 @@ impl SourceParams {
         self.x_resolution = Some(x_resolution);
         self.y_resolution = Some(y_resolution);
     }
+
+    pub fn set_icc_profile(&mut self, icc_profile: Option<Vec<u8>>) {
+        self.icc_profile = icc_profile;
+    }
+
+    /// 水平、垂直方向的分辨率（DPI），未读取或无法换算时返回 `None`
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        // TIFF ResolutionUnit：2 为英寸，3 为厘米
+        let scale = match self.resolution_unit {
+            2 => 1.0,
+            3 => 2.54,
+            _ => return None,
+        };
+        let value = |resolution: &Option<TiffValue>| match resolution.as_ref()? {
+            TiffValue::Rational(n, d) if *d > 0 => Some(*n as f64 / *d as f64),
+            TiffValue::Unsigned(v) => Some(*v as f64),
+            TiffValue::Float(v) => Some(*v as f64),
+            TiffValue::Double(v) => Some(*v),
+            _ => None,
+        };
+        let (x, y) = (value(&self.x_resolution)?, value(&self.y_resolution)?);
+        (x > 0.0 && y > 0.0).then_some((x * scale, y * scale))
+    }
+}
+
+impl OutputInfo {
+    /// 输出图像的颜色类型
+    pub fn color_type(&self) -> Option<tiff::ColorType> {
+        if self.cmyk_separation.is_some() {
+            Some(tiff::ColorType::CMYK(8))
+        } else {
+            self.source_params.color_type
+        }
+    }
+
+    /// 写入输出图像的 ICC 配置文件
+    ///
+    /// 分色后输入图像中的 RGB 配置文件不再适用，未指定配置文件时不嵌入。
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        match &self.icc_override {
+            Some(icc_profile) => Some(icc_profile),
+            None if self.cmyk_separation.is_none() => self.source_params.icc_profile.as_deref(),
+            None => None,
+        }
+    }
 }
 
 /// 计算输出图像信息
-pub fn calc_output_info<R>(
-    inputs: &mut [InputImageContext<R>],
+pub fn calc_output_info<D>(
+    inputs: &mut [InputImageContext<D>],
     options: &ProcessOptions,
 ) -> Result<OutputInfo>
 where
-    R: Read + Seek,
+    D: FrameDecoder,
 {
     if inputs.is_empty() {
         return Err(Error::InvalidInput("输入图像数量不可为空".to_string()));
     }
 
-    let mut params = Params::new(options.lpi, options.physical_width_cm);
+    if options.physical_width.is_none() && options.physical_height.is_none() {
+        return Err(Error::InvalidInput(
+            "输出图像的物理宽度与高度至少指定一项".to_string(),
+        ));
+    }
+
+    let mut params = Params::new(options.lpi, options.physical_width, options.physical_height);
 
     // 读取第一张图作为基准
     let first_input = &mut inputs[0];
-    let mut decoder = tiff::decoder::Decoder::new(&mut first_input.reader).unwrap();
     debug!("Reading first image as baseline");
 
     {
-        let source_params = read_params_from_tiff(&mut decoder, true)?;
+        let source_params = first_input.decoder.read_params(true)?;
         debug!("color type: {:?}", source_params.color_type);
         debug!(
             "dimensions: {}x{}",
@@ -138,62 +242,609 @@ where
         debug!("resolution_unit: {}", source_params.resolution_unit);
         debug!("x_resolution: {:?}", source_params.x_resolution);
         debug!("y_resolution: {:?}", source_params.y_resolution);
+        debug!(
+            "icc_profile: {:?} bytes",
+            source_params.icc_profile.as_ref().map(|p| p.len())
+        );
         params.source_params = source_params;
     }
+    let mut frame_sizes = vec![(params.source_params.width, params.source_params.height)];
+    for input in &mut inputs[1..] {
+        let frame_params = input.decoder.read_params(false)?;
+        frame_sizes.push((frame_params.width, frame_params.height));
+    }
 
-    // 还原状态
-    drop(decoder);
-    first_input.reader.seek(SeekFrom::Start(0))?;
+    // 仅对 RGB 输入分色
+    let cmyk_separation = options.cmyk_separation.filter(|_| {
+        matches!(
+            params.source_params.color_type,
+            Some(tiff::ColorType::RGB(8 | 16))
+        )
+    });
 
-    // 有效输入像素宽度
-    let lenticular_width_px: u32 = inputs
+    // 各帧光栅宽度，背面印刷时光栅内的帧顺序相反
+    let mut lenticular_widths = inputs
         .iter()
         .map(|c| c.image_options().lenticular_width_px)
-        .sum();
+        .collect::<Vec<_>>();
+    if options.reverse_print {
+        lenticular_widths.reverse();
+    }
+    // 有效输入像素宽度
+    let lenticular_width_px: u32 = lenticular_widths.iter().sum();
     // 光栅线数
-    let lenticular_count = (params.physical_width_in() * params.lpi).floor() as u32;
+    let lenticular_count = (params.physical_width().to_inches() * params.lpi).floor() as u32;
     // 原图宽高比
-    let ratio = params.source_params.width as f64 / params.source_params.height as f64;
+    let ratio = params.source_ratio();
     // 输出图像宽度
     let output_width_px = lenticular_width_px * lenticular_count;
-    // 输出图像高度
-    let output_height_px = (output_width_px as f64 / ratio).round() as u32;
     // 输出图像DPI
-    let dpi = output_width_px as f64 / params.physical_width_in();
+    let dpi = output_width_px as f64 / params.physical_width().to_inches();
+    // 输出图像高度
+    let output_height_px = match params.physical_height {
+        Some(height) => height.to_px(dpi).round() as u32,
+        None => (output_width_px as f64 / ratio).round() as u32,
+    };
+    let output_ratio = output_width_px as f64 / output_height_px as f64;
+    if options.fit_mode == FitMode::Stretch && (output_ratio / ratio - 1.0).abs() > 0.01 {
+        warn!(
+            "输出画幅宽高比 {:.3} 与原图 {:.3} 不一致，图像将被拉伸",
+            output_ratio, ratio
+        );
+    }
     // let dpi = lenticular_width_px as f64 / (1. / params.lpi);
     // let dpi = lenticular_width_px as f64 * params.lpi;
 
-    Ok(OutputInfo {
-        width: output_width_px,
-        height: output_height_px,
+    // 出血：水平方向取整数个光栅，保证成品边缘落在光栅边界上
+    let bleed = options.bleed.unwrap_or_default();
+    let bleed_x = (bleed.to_inches() * params.lpi).ceil() as u32 * lenticular_width_px;
+    let bleed_y = bleed.to_px(dpi).round() as u32;
+    let safe_margin = options
+        .safe_margin
+        .map(|margin| margin.to_px(dpi).round() as u32);
+    if safe_margin.is_some_and(|margin| margin * 2 >= output_width_px.min(output_height_px)) {
+        return Err(Error::InvalidInput("安全边距超出成品尺寸".to_string()));
+    }
+    let layout = PageLayout {
+        trim_width: output_width_px,
+        trim_height: output_height_px,
+        bleed_x,
+        bleed_y,
+        safe_margin,
+        crop_marks: options.crop_marks.then(|| CropMarks::with_dpi(dpi)),
+        registration_bars: options
+            .registration_bars
+            .then(|| RegistrationBars::with_dpi(dpi)),
+        lenticular_widths,
+        reverse_print: options.reverse_print,
+    };
+    debug!("layout: {:?}", layout);
+
+    let output_info = OutputInfo {
+        width: layout.bleed_width(),
+        height: layout.bleed_height(),
         dpi_w: dpi,
         dpi_h: dpi,
+        physical_width: params.physical_width(),
+        physical_height: Length::from_px(output_height_px as f64, dpi),
+        icc_override: options.icc_profile.clone(),
+        cmyk_separation,
+        lzw: options.lzw,
+        fit_mode: options.fit_mode,
+        frame_sizes,
+        layout,
         source_params: params.source_params,
-    })
+    };
+    if let Some(icc_profile) = output_info.icc_profile() {
+        check_icc_profile(icc_profile, output_info.color_type())?;
+    }
+    Ok(output_info)
+}
+
+/// 可参与光栅合成的像素类型
+pub trait InterlaceColor: Color + Copy + Send + Sync + 'static {
+    /// 对应的 TIFF 颜色类型
+    const COLOR_TYPE: tiff::ColorType;
+
+    /// 取出解码结果中的原始字节，采样位深不符时返回 `None`
+    fn raw_bytes(data: FrameData) -> Option<Vec<u8>>;
+
+    /// 取出图像的原始字节，格式同 [`InterlaceColor::raw_bytes`]
+    fn image_bytes(img: &MatrixImage<Self>) -> Vec<u8>;
+
+    /// 由 RGB 颜色转换，CMYK 类型按分色参数转换
+    fn from_rgb8(color: Rgb8Color, separation: &CmykSeparation) -> Self;
+
+    /// 缩放原始字节图像
+    fn resize(
+        src: Vec<u8>,
+        width: u32,
+        height: u32,
+        out_width: u32,
+        out_height: u32,
+        alg: ScaleAlgorithm,
+    ) -> Result<Vec<u8>>;
+}
+
+impl InterlaceColor for Cmyk8Color {
+    const COLOR_TYPE: tiff::ColorType = tiff::ColorType::CMYK(8);
+
+    fn raw_bytes(data: FrameData) -> Option<Vec<u8>> {
+        match data {
+            FrameData::U8(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn image_bytes(img: &MatrixImage<Self>) -> Vec<u8> {
+        img.to_bytes()
+    }
+
+    fn from_rgb8(color: Rgb8Color, separation: &CmykSeparation) -> Self {
+        separation.separate_rgb8(color)
+    }
+
+    fn resize(
+        src: Vec<u8>,
+        width: u32,
+        height: u32,
+        out_width: u32,
+        out_height: u32,
+        alg: ScaleAlgorithm,
+    ) -> Result<Vec<u8>> {
+        resize_cmyk8(src, width, height, out_width, out_height, alg.into())
+    }
+}
+
+impl InterlaceColor for Cmyk16Color {
+    const COLOR_TYPE: tiff::ColorType = tiff::ColorType::CMYK(16);
+
+    fn raw_bytes(data: FrameData) -> Option<Vec<u8>> {
+        match data {
+            FrameData::U16(data) => Some(u16_to_ne_bytes(&data)),
+            _ => None,
+        }
+    }
+
+    fn image_bytes(img: &MatrixImage<Self>) -> Vec<u8> {
+        u16_to_ne_bytes(&img.to_samples())
+    }
+
+    fn from_rgb8(color: Rgb8Color, separation: &CmykSeparation) -> Self {
+        let [c, m, y, k] = separation
+            .separate(
+                color.r as f64 / 255.0,
+                color.g as f64 / 255.0,
+                color.b as f64 / 255.0,
+            )
+            .map(|v| (v * 65535.0).round().clamp(0.0, 65535.0) as u16);
+        Cmyk16Color { c, m, y, k }
+    }
+
+    fn resize(
+        src: Vec<u8>,
+        width: u32,
+        height: u32,
+        out_width: u32,
+        out_height: u32,
+        alg: ScaleAlgorithm,
+    ) -> Result<Vec<u8>> {
+        resize_cmyk16(src, width, height, out_width, out_height, alg.into())
+    }
+}
+
+impl InterlaceColor for Rgb8Color {
+    const COLOR_TYPE: tiff::ColorType = tiff::ColorType::RGB(8);
+
+    fn raw_bytes(data: FrameData) -> Option<Vec<u8>> {
+        match data {
+            FrameData::U8(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn image_bytes(img: &MatrixImage<Self>) -> Vec<u8> {
+        img.to_bytes()
+    }
+
+    fn from_rgb8(color: Rgb8Color, _separation: &CmykSeparation) -> Self {
+        color
+    }
+
+    fn resize(
+        src: Vec<u8>,
+        width: u32,
+        height: u32,
+        out_width: u32,
+        out_height: u32,
+        alg: ScaleAlgorithm,
+    ) -> Result<Vec<u8>> {
+        resize_rgb8(src, width, height, out_width, out_height, alg.into())
+    }
+}
+
+impl InterlaceColor for Rgb16Color {
+    const COLOR_TYPE: tiff::ColorType = tiff::ColorType::RGB(16);
+
+    fn raw_bytes(data: FrameData) -> Option<Vec<u8>> {
+        match data {
+            FrameData::U16(data) => Some(u16_to_ne_bytes(&data)),
+            _ => None,
+        }
+    }
+
+    fn image_bytes(img: &MatrixImage<Self>) -> Vec<u8> {
+        u16_to_ne_bytes(&img.to_samples())
+    }
+
+    fn from_rgb8(color: Rgb8Color, _separation: &CmykSeparation) -> Self {
+        Rgb16Color {
+            r: color.r as u16 * 257,
+            g: color.g as u16 * 257,
+            b: color.b as u16 * 257,
+        }
+    }
+
+    fn resize(
+        src: Vec<u8>,
+        width: u32,
+        height: u32,
+        out_width: u32,
+        out_height: u32,
+        alg: ScaleAlgorithm,
+    ) -> Result<Vec<u8>> {
+        resize_rgb16(src, width, height, out_width, out_height, alg.into())
+    }
+}
+
+impl InterlaceColor for Gray8Color {
+    const COLOR_TYPE: tiff::ColorType = tiff::ColorType::Gray(8);
+
+    fn raw_bytes(data: FrameData) -> Option<Vec<u8>> {
+        match data {
+            FrameData::U8(data) => Some(data),
+            _ => None,
+        }
+    }
+
+    fn image_bytes(img: &MatrixImage<Self>) -> Vec<u8> {
+        img.to_bytes()
+    }
+
+    /// 取亮度，白色背景对应满版白墨
+    fn from_rgb8(color: Rgb8Color, _separation: &CmykSeparation) -> Self {
+        Gray8Color {
+            v: rgb8_luma(color.r, color.g, color.b),
+        }
+    }
+
+    fn resize(
+        src: Vec<u8>,
+        width: u32,
+        height: u32,
+        out_width: u32,
+        out_height: u32,
+        alg: ScaleAlgorithm,
+    ) -> Result<Vec<u8>> {
+        resize_gray8(src, width, height, out_width, out_height, alg.into())
+    }
+}
+
+/// BT.601 亮度
+pub(crate) fn rgb8_luma(r: u8, g: u8, b: u8) -> u8 {
+    (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64).round() as u8
+}
+
+/// 将 RGB 帧分色为 CMYK 8位，16位输入按全精度分色
+fn separate_frame(frame: FrameData, separation: &CmykSeparation) -> FrameData {
+    let cmyk = match frame {
+        FrameData::U8(data) => data
+            .chunks(3)
+            .flat_map(|px| {
+                let c = separation.separate_rgb8(Rgb8Color {
+                    r: px[0],
+                    g: px[1],
+                    b: px[2],
+                });
+                [c.c, c.m, c.y, c.k]
+            })
+            .collect(),
+        FrameData::U16(data) => data
+            .chunks(3)
+            .flat_map(|px| {
+                let c = separation.separate_rgb16(Rgb16Color {
+                    r: px[0],
+                    g: px[1],
+                    b: px[2],
+                });
+                [c.c, c.m, c.y, c.k]
+            })
+            .collect(),
+    };
+    FrameData::U8(cmyk)
+}
+
+/// 像素矩形区域
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rect {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Rect {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        }
+    }
+}
+
+/// 计算原图的裁切区域，以及缩放后在输出图像中的位置
+fn fit_layout(fit_mode: FitMode, src: (u32, u32), out: (u32, u32)) -> (Rect, Rect) {
+    let (src_w, src_h) = src;
+    let (out_w, out_h) = out;
+    let scale_x = out_w as f64 / src_w as f64;
+    let scale_y = out_h as f64 / src_h as f64;
+
+    match fit_mode {
+        FitMode::Stretch => (Rect::new(src_w, src_h), Rect::new(out_w, out_h)),
+        FitMode::Fill { anchor } => {
+            let scale = scale_x.max(scale_y);
+            let crop_w = ((out_w as f64 / scale).round() as u32).clamp(1, src_w);
+            let crop_h = ((out_h as f64 / scale).round() as u32).clamp(1, src_h);
+            let (fx, fy) = anchor.factors();
+            let crop = Rect {
+                x: ((src_w - crop_w) as f64 * fx).round() as u32,
+                y: ((src_h - crop_h) as f64 * fy).round() as u32,
+                width: crop_w,
+                height: crop_h,
+            };
+            (crop, Rect::new(out_w, out_h))
+        }
+        FitMode::Fit { .. } => {
+            let scale = scale_x.min(scale_y);
+            let dest_w = ((src_w as f64 * scale).round() as u32).clamp(1, out_w);
+            let dest_h = ((src_h as f64 * scale).round() as u32).clamp(1, out_h);
+            let dest = Rect {
+                x: (out_w - dest_w) / 2,
+                y: (out_h - dest_h) / 2,
+                width: dest_w,
+                height: dest_h,
+            };
+            (Rect::new(src_w, src_h), dest)
+        }
+    }
+}
+
+/// 按适配方式裁切、缩放原图到成品尺寸，再将边缘像素延伸到出血区域
+fn fit_frame<C>(
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    output_info: &OutputInfo,
+    background: C,
+    scale_alg: ScaleAlgorithm,
+) -> Result<MatrixImage<C>>
+where
+    C: InterlaceColor,
+{
+    let layout = &output_info.layout;
+    let trim = (layout.trim_width, layout.trim_height);
+    let (crop, dest) = fit_layout(output_info.fit_mode, (width, height), trim);
+
+    let data = crop_raw(data, width, height, &crop);
+    let resized = C::resize(
+        data,
+        crop.width,
+        crop.height,
+        dest.width,
+        dest.height,
+        scale_alg,
+    )?;
+    let img: MatrixImage<C> = MatrixImage::from_slice(&resized, dest.width, dest.height)?;
+    let img = if dest == Rect::new(trim.0, trim.1) {
+        img
+    } else {
+        let mut out: MatrixImage<C> = MatrixImage::new(trim.0, trim.1);
+        out.inner_mut().fill(background);
+        out.inner_mut()
+            .slice_mut(s![
+                dest.y as usize..(dest.y + dest.height) as usize,
+                dest.x as usize..(dest.x + dest.width) as usize
+            ])
+            .assign(img.inner());
+        out
+    };
+    Ok(extend_into_bleed(img, layout))
+}
+
+/// 复制成品边缘的像素填充四周出血，成品区域内的画面不受出血影响
+fn extend_into_bleed<C>(img: MatrixImage<C>, layout: &PageLayout) -> MatrixImage<C>
+where
+    C: InterlaceColor,
+{
+    if layout.bleed_x == 0 && layout.bleed_y == 0 {
+        return img;
+    }
+
+    let (bleed_x, bleed_y) = (layout.bleed_x as usize, layout.bleed_y as usize);
+    let (last_x, last_y) = (img.width() as usize - 1, img.height() as usize - 1);
+    let src = img.inner();
+    let mut out: MatrixImage<C> = MatrixImage::new(layout.bleed_width(), layout.bleed_height());
+    out.inner_mut().indexed_iter_mut().for_each(|((y, x), px)| {
+        *px = src[[
+            y.saturating_sub(bleed_y).min(last_y),
+            x.saturating_sub(bleed_x).min(last_x),
+        ]];
+    });
+    out
+}
+
+/// 读取一帧并按输出图像适配
+fn read_fitted_frame<C, D>(
+    decoder: &mut D,
+    params: &SourceParams,
+    output_info: &OutputInfo,
+    background: C,
+    scale_alg: ScaleAlgorithm,
+) -> Result<MatrixImage<C>>
+where
+    C: InterlaceColor,
+    D: FrameDecoder,
+{
+    let mut frame = decoder.read_image()?;
+    if let Some(separation) = &output_info.cmyk_separation {
+        frame = separate_frame(frame, separation);
+    }
+    let Some(img_res) = C::raw_bytes(frame) else {
+        return Err(Error::InvalidInput(format!(
+            "图像数据读取失败: 非预期的编码类型，仅接受 {:?} 图像",
+            C::COLOR_TYPE
+        )));
+    };
+    fit_frame(
+        img_res,
+        params.width,
+        params.height,
+        output_info,
+        background,
+        scale_alg,
+    )
+}
+
+/// 裁切原始字节图像
+fn crop_raw(data: Vec<u8>, width: u32, height: u32, rect: &Rect) -> Vec<u8> {
+    if *rect == Rect::new(width, height) {
+        return data;
+    }
+
+    let pixel_bytes = data.len() / (width as usize * height as usize);
+    let row_bytes = width as usize * pixel_bytes;
+    (rect.y..rect.y + rect.height)
+        .flat_map(|y| {
+            let start = y as usize * row_bytes + rect.x as usize * pixel_bytes;
+            &data[start..start + rect.width as usize * pixel_bytes]
+        })
+        .copied()
+        .collect()
+}
+
+fn u16_to_ne_bytes(data: &[u16]) -> Vec<u8> {
+    data.iter().flat_map(|v| v.to_ne_bytes()).collect()
 }
 
 /// 处理CMYK8图像
-pub fn process_tiff_cmyk8<R>(
-    mut inputs: Vec<InputImageContext<R>>,
+pub fn process_tiff_cmyk8<D>(
+    inputs: Vec<InputImageContext<D>>,
     output_info: &OutputInfo,
     scale_alg: ScaleAlgorithm,
 ) -> Result<MatrixImage<Cmyk8Color>>
 where
-    R: Read + Seek,
+    D: FrameDecoder,
+{
+    process_tiff(inputs, output_info, scale_alg)
+}
+
+/// 处理CMYK16图像
+pub fn process_tiff_cmyk16<D>(
+    inputs: Vec<InputImageContext<D>>,
+    output_info: &OutputInfo,
+    scale_alg: ScaleAlgorithm,
+) -> Result<MatrixImage<Cmyk16Color>>
+where
+    D: FrameDecoder,
+{
+    process_tiff(inputs, output_info, scale_alg)
+}
+
+/// 处理RGB8图像
+pub fn process_tiff_rgb8<D>(
+    inputs: Vec<InputImageContext<D>>,
+    output_info: &OutputInfo,
+    scale_alg: ScaleAlgorithm,
+) -> Result<MatrixImage<Rgb8Color>>
+where
+    D: FrameDecoder,
+{
+    process_tiff(inputs, output_info, scale_alg)
+}
+
+/// 处理RGB16图像
+pub fn process_tiff_rgb16<D>(
+    inputs: Vec<InputImageContext<D>>,
+    output_info: &OutputInfo,
+    scale_alg: ScaleAlgorithm,
+) -> Result<MatrixImage<Rgb16Color>>
+where
+    D: FrameDecoder,
+{
+    process_tiff(inputs, output_info, scale_alg)
+}
+
+/// 按像素类型处理图像
+pub fn process_tiff<C, D>(
+    inputs: Vec<InputImageContext<D>>,
+    output_info: &OutputInfo,
+    scale_alg: ScaleAlgorithm,
+) -> Result<MatrixImage<C>>
+where
+    C: InterlaceColor,
+    D: FrameDecoder,
+{
+    process_tiff_cached(inputs, output_info, scale_alg, None)
+}
+
+/// 同 [`process_tiff`]，指定了缓存键的帧从 `cache` 中复用适配结果
+pub fn process_tiff_cached<C, D>(
+    mut inputs: Vec<InputImageContext<D>>,
+    output_info: &OutputInfo,
+    scale_alg: ScaleAlgorithm,
+    cache: Option<&FittedFrameCache>,
+) -> Result<MatrixImage<C>>
+where
+    C: InterlaceColor,
+    D: FrameDecoder,
 {
     if inputs.is_empty() {
         return Err(Error::InvalidInput("输入图像数量不可为空".to_string()));
     }
+    if output_info.color_type() != Some(C::COLOR_TYPE) {
+        return Err(Error::InvalidInput(format!(
+            "非预期的颜色类型: 预期：{:?}, 实际输出：{:?}",
+            C::COLOR_TYPE,
+            output_info.color_type(),
+        )));
+    }
 
     // 各种参数
     let lenticular_width_table = inputs
         .iter()
         .map(|c| c.image_options.lenticular_width_px)
         .collect::<Vec<_>>();
+    let reverse_print = output_info.layout.reverse_print;
+    let lenticular_width_table = if reverse_print {
+        lenticular_width_table.into_iter().rev().collect()
+    } else {
+        lenticular_width_table
+    };
+    let frame_count = inputs.len();
+
+    // 适配留白的背景色
+    let background = match output_info.fit_mode {
+        FitMode::Fit { background } => {
+            C::from_rgb8(background, &output_info.cmyk_separation.unwrap_or_default())
+        }
+        _ => C::default(),
+    };
+    let signature = FitSignature::new::<C>(output_info, scale_alg);
 
     // 创建输出图像
-    let mut output_img: MatrixImage<Cmyk8Color> =
-        MatrixImage::new(output_info.width, output_info.height);
+    let mut output_img: MatrixImage<C> = MatrixImage::new(output_info.width, output_info.height);
     debug!(
         "output image: {}x{}",
         output_img.width(),
@@ -204,46 +855,67 @@ where
         .iter_mut()
         .enumerate()
         .try_for_each(|(input_index, input_ctx)| -> Result<()> {
-            let mut decoder = tiff::decoder::Decoder::new(&mut input_ctx.reader)?;
-            let img_params = read_params_from_tiff(&mut decoder, false)?;
+            let img_params = input_ctx.decoder.read_params(false)?;
             debug!("Image {:02} source: params: {:?}", input_index, img_params);
-            if !is_matching_params(&output_info.source_params, &img_params) {
+            let matching = match output_info.fit_mode {
+                FitMode::Stretch => is_matching_params(&output_info.source_params, &img_params),
+                _ => is_matching_color_type(&output_info.source_params, &img_params),
+            };
+            if !matching {
                 return Err(Error::InvalidInput(format!(
                     "输入图像参数与基准图像参数不匹配: 预期：{:?}, 实际输入：{:?}",
                     output_info.source_params, img_params,
                 )));
             }
+            if output_info.source_params.icc_profile != img_params.icc_profile {
+                return Err(Error::InvalidInput(format!(
+                    "输入图像 {:02} 的 ICC 配置文件与基准图像不一致",
+                    input_index
+                )));
+            }
 
-            // 读取图像数据
-            let TiffDecodingResult::U8(img_res) = decoder.read_image()? else {
-                return Err(Error::InvalidInput(
-                    "图像数据读取失败: 非预期的编码类型，仅接受 CMYK 8位图像".to_string(),
-                ));
+            // 对原图进行裁切、缩放，指定缓存键时复用已适配的帧
+            let cached = cache
+                .zip(input_ctx.cache_key.as_deref())
+                .and_then(|(cache, key)| cache.get::<C>(key, &signature));
+            let input_img = match cached {
+                Some(img) => {
+                    debug!("Image {:02}: using cached frame", input_index);
+                    img
+                }
+                None => {
+                    let img = Arc::new(read_fitted_frame(
+                        &mut input_ctx.decoder,
+                        &img_params,
+                        output_info,
+                        background,
+                        scale_alg,
+                    )?);
+                    if let (Some(cache), Some(key)) = (cache, &input_ctx.cache_key) {
+                        cache.insert(key, signature.clone(), img.clone());
+                    }
+                    img
+                }
             };
-            // 对原图进行缩放
-            let resized_res = resize_cmyk8(
-                img_res,
-                img_params.width,
-                img_params.height,
-                output_info.width,
-                output_info.height,
-                scale_alg.into(),
-            )?;
             debug!(
                 "Image {:02} resized: {}x{}",
                 input_index, output_info.width, output_info.height
             );
-            // 创建矩阵图像封装
-            let input_img: MatrixImage<Cmyk8Color> =
-                MatrixImage::from_slice(&resized_res, output_info.width, output_info.height)?;
 
-            // 写入输出图像
-            let input_mat = input_img.inner();
+            // 写入输出图像，背面印刷时镜像帧并使用反向的光栅宽度表
+            let (input_mat, table_index) = if reverse_print {
+                (
+                    input_img.inner().slice(s![.., ..;-1]),
+                    frame_count - 1 - input_index,
+                )
+            } else {
+                (input_img.inner().view(), input_index)
+            };
             let output_mat = output_img.inner_mut();
             let col_mapping = create_line_index_mapping_advanced(
                 input_img.width(),
                 &lenticular_width_table,
-                input_index,
+                table_index,
             );
             for col_index in col_mapping {
                 if col_index >= input_img.width() {
@@ -268,20 +940,206 @@ where
         dpi_h: output_info.dpi_h,
         dpi_w: output_info.dpi_w,
     });
+    output_img.set_icc_profile(output_info.icc_profile().map(<[u8]>::to_vec));
+    output_img.set_layout(Some(output_info.layout.clone()));
+    output_img.set_lzw(output_info.lzw);
 
     Ok(output_img)
 }
 
+/// 写出 CMYK 8位图像，图像带有版面信息时同时绘制裁切标记与安全边距参考线
 pub fn write_tiff_cmyk8<W>(writer: W, out: &MatrixImage<Cmyk8Color>) -> Result<()>
 where
     W: Write + Seek,
 {
-    let mut out_encoder = tiff::encoder::TiffEncoder::new(writer)?;
+    let marked;
+    let out = match out.layout() {
+        Some(layout) if layout.has_marks() => {
+            marked = apply_print_marks(out, layout);
+            &marked
+        }
+        _ => out,
+    };
+    write_tiff::<_, _, colortype::CMYK8>(writer, out, &[], &out.to_bytes())
+}
 
-    let mut out_tiff_img = out_encoder.new_image::<colortype::CMYK8>(out.width(), out.height())?;
+/// 写出 CMYK 8位加白墨通道的 5 通道图像，白墨作为第 5 个油墨通道
+///
+/// 按 TIFF 分色图像的多油墨约定写入 InkSet=2、NumberOfInks=5 与各通道的 InkNames。
+///
+/// 白墨通道的尺寸与版面必须与 CMYK 图像一致，标记区域内不铺白墨。
+pub fn write_tiff_cmykw8<W>(
+    writer: W,
+    out: &MatrixImage<Cmyk8Color>,
+    white: &MatrixImage<Gray8Color>,
+) -> Result<()>
+where
+    W: Write + Seek,
+{
+    if (out.width(), out.height()) != (white.width(), white.height()) {
+        return Err(Error::InvalidInput(format!(
+            "白墨通道尺寸 {}x{} 与图像尺寸 {}x{} 不一致",
+            white.width(),
+            white.height(),
+            out.width(),
+            out.height()
+        )));
+    }
+    let (marked, white_marked);
+    let (out, white) = match out.layout() {
+        Some(layout) if layout.has_marks() => {
+            marked = apply_print_marks(out, layout);
+            white_marked = pad_slug(white, layout);
+            (&marked, &white_marked)
+        }
+        _ => (out, white),
+    };
+    let data = out
+        .inner()
+        .iter()
+        .zip(white.inner().iter())
+        .flat_map(|(c, w)| [c.c, c.m, c.y, c.k, w.v])
+        .collect::<Vec<u8>>();
+    write_tiff::<_, _, Cmykw8>(writer, out, &WHITE_INK_NAMES, &data)
+}
 
+/// 写出单通道 8位图像（如独立的白墨蒙版），带有版面信息时补齐标记区域以与 CMYK 输出对齐
+pub fn write_tiff_gray8<W>(writer: W, out: &MatrixImage<Gray8Color>) -> Result<()>
+where
+    W: Write + Seek,
+{
+    let padded;
+    let out = match out.layout() {
+        Some(layout) if layout.has_marks() => {
+            padded = pad_slug(out, layout);
+            &padded
+        }
+        _ => out,
+    };
+    write_tiff::<_, _, colortype::Gray8>(writer, out, &[], &out.to_bytes())
+}
+
+/// 写出 1 位分色版（WhiteIsZero，1 表示着墨），不压缩
+pub fn write_tiff_bitmap<W>(writer: W, bitmap: &Bitmap) -> Result<()>
+where
+    W: Write + Seek,
+{
+    let mut encoder = tiff::encoder::TiffEncoder::new(writer)?;
+    let mut dir = encoder.new_directory()?;
+    let offset = dir.write_data(&bitmap.data[..])?;
+
+    dir.write_tag(TiffTag::ImageWidth, bitmap.width)?;
+    dir.write_tag(TiffTag::ImageLength, bitmap.height)?;
+    dir.write_tag(TiffTag::BitsPerSample, 1u16)?;
+    dir.write_tag(TiffTag::Compression, 1u16)?;
+    dir.write_tag(
+        TiffTag::PhotometricInterpretation,
+        PhotometricInterpretation::WhiteIsZero.to_u16(),
+    )?;
+    dir.write_tag(TiffTag::StripOffsets, offset as u32)?;
+    dir.write_tag(TiffTag::SamplesPerPixel, 1u16)?;
+    dir.write_tag(TiffTag::RowsPerStrip, bitmap.height)?;
+    dir.write_tag(TiffTag::StripByteCounts, bitmap.data.len() as u32)?;
+    dir.write_tag(
+        TiffTag::Software,
+        concat!("lenticular-image-tool", " ", env!("CARGO_PKG_VERSION")),
+    )?;
+    dir.write_tag(TiffTag::ResolutionUnit, 2u16)?;
+    dir.write_tag(
+        TiffTag::XResolution,
+        Rational {
+            n: (bitmap.dpi_w * 10000.0) as u32,
+            d: 10000,
+        },
+    )?;
+    dir.write_tag(
+        TiffTag::YResolution,
+        Rational {
+            n: (bitmap.dpi_h * 10000.0) as u32,
+            d: 10000,
+        },
+    )?;
+    dir.finish()?;
+
+    Ok(())
+}
+
+/// CMYK 8位加白墨的 5 色油墨
+struct Cmykw8;
+
+/// [`Cmykw8`] 各通道的油墨名称
+const WHITE_INK_NAMES: [&str; 5] = ["Cyan", "Magenta", "Yellow", "Black", "White"];
+
+impl colortype::ColorType for Cmykw8 {
+    type Inner = u8;
+    const TIFF_VALUE: PhotometricInterpretation = PhotometricInterpretation::CMYK;
+    const BITS_PER_SAMPLE: &'static [u16] = &[8; 5];
+    const SAMPLE_FORMAT: &'static [SampleFormat] = &[SampleFormat::Uint; 5];
+}
+
+pub fn write_tiff_cmyk16<W>(writer: W, out: &MatrixImage<Cmyk16Color>) -> Result<()>
+where
+    W: Write + Seek,
+{
+    write_tiff::<_, _, colortype::CMYK16>(writer, out, &[], &out.to_samples())
+}
+
+pub fn write_tiff_rgb8<W>(writer: W, out: &MatrixImage<Rgb8Color>) -> Result<()>
+where
+    W: Write + Seek,
+{
+    write_tiff::<_, _, colortype::RGB8>(writer, out, &[], &out.to_bytes())
+}
+
+pub fn write_tiff_rgb16<W>(writer: W, out: &MatrixImage<Rgb16Color>) -> Result<()>
+where
+    W: Write + Seek,
+{
+    write_tiff::<_, _, colortype::RGB16>(writer, out, &[], &out.to_samples())
+}
+
+/// 按 `out` 的尺寸、分辨率、ICC 配置文件与压缩方式写出 `data`
+fn write_tiff<W, C, T>(
+    writer: W,
+    out: &MatrixImage<C>,
+    ink_names: &[&str],
+    data: &[T::Inner],
+) -> Result<()>
+where
+    W: Write + Seek,
+    C: Color,
+    T: colortype::ColorType,
+    [T::Inner]: TiffEncodeValue,
+{
+    let mut out_encoder = tiff::encoder::TiffEncoder::new(writer)?;
+    let (width, height) = (out.width(), out.height());
+    let (info, icc_profile) = (out.info(), out.icc_profile());
+    if out.lzw() {
+        let image = out_encoder.new_image_with_compression::<T, _>(width, height, Lzw)?;
+        write_image(image, info, icc_profile, ink_names, data)
+    } else {
+        let image = out_encoder.new_image_with_compression::<T, _>(width, height, Uncompressed)?;
+        write_image(image, info, icc_profile, ink_names, data)
+    }
+}
+
+/// 写入元数据与图像数据
+fn write_image<W, T, K, D>(
+    mut out_tiff_img: ImageEncoder<W, T, K, D>,
+    info: Option<&DpiInfo>,
+    icc_profile: Option<&[u8]>,
+    ink_names: &[&str],
+    data: &[T::Inner],
+) -> Result<()>
+where
+    W: Write + Seek,
+    T: colortype::ColorType,
+    K: TiffKind,
+    D: Compression,
+    [T::Inner]: TiffEncodeValue,
+{
     // 写入元数据
-    if let Some(info) = out.info() {
+    if let Some(info) = info {
         let dpi_w_n = (info.dpi_w * 10000.0) as u32;
         let dpi_h_n = (info.dpi_h * 10000.0) as u32;
         debug!(
@@ -295,7 +1153,7 @@ where
             TiffTag::Software,
             concat!("lenticular-image-tool", " ", env!("CARGO_PKG_VERSION")),
         )?;
-        e.write_tag(TiffTag::ResolutionUnit, 2)?;
+        e.write_tag(TiffTag::ResolutionUnit, 2u16)?;
         e.write_tag(
             TiffTag::XResolution,
             Rational {
@@ -313,48 +1171,121 @@ where
     } else {
         warn!("图像信息缺失，无法写入 TIFF 信息");
     }
+    if let Some(icc_profile) = icc_profile {
+        out_tiff_img
+            .encoder()
+            .write_tag(ICC_PROFILE_TAG, IccProfileValue(icc_profile))?;
+    }
+    if !ink_names.is_empty() {
+        // InkSet 2：非 CMYK 油墨组合，油墨数量与名称由 NumberOfInks、InkNames 给出
+        let names = ink_names
+            .iter()
+            .flat_map(|name| str::bytes(name).chain([0]))
+            .collect::<Vec<u8>>();
+        let e = out_tiff_img.encoder();
+        e.write_tag(INK_SET_TAG, 2u16)?;
+        e.write_tag(INK_NAMES_TAG, InkNamesValue(&names))?;
+        e.write_tag(NUMBER_OF_INKS_TAG, ink_names.len() as u16)?;
+    }
 
-    out_tiff_img.write_data(&out.to_bytes())?;
+    out_tiff_img.write_data(data)?;
 
     Ok(())
 }
 
-/// 从解码器中读取图片元数据参数
-fn read_params_from_tiff<R>(
-    decoder: &mut tiff::decoder::Decoder<R>,
-    read_tags: bool,
-) -> Result<SourceParams>
-where
-    R: Read + Seek,
-{
-    let mut params = SourceParams::default();
+/// ICC 配置文件标签（InterColorProfile）
+pub(crate) const ICC_PROFILE_TAG: TiffTag = TiffTag::Unknown(34675);
+
+/// 分色图像的油墨组合（InkSet）
+const INK_SET_TAG: TiffTag = TiffTag::Unknown(332);
+/// 各油墨的名称（InkNames），以 NUL 分隔
+const INK_NAMES_TAG: TiffTag = TiffTag::Unknown(333);
+/// 油墨数量（NumberOfInks）
+const NUMBER_OF_INKS_TAG: TiffTag = TiffTag::Unknown(334);
+
+/// 以 ASCII 类型写入以 NUL 分隔、结尾的多个油墨名称
+struct InkNamesValue<'a>(&'a [u8]);
+
+impl TiffEncodeValue for InkNamesValue<'_> {
+    const BYTE_LEN: u8 = 1;
+    const FIELD_TYPE: TiffType = TiffType::ASCII;
+
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+
+    fn data(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Borrowed(self.0)
+    }
+}
+
+/// 按 TIFF 规范以 UNDEFINED 类型写入 ICC 配置文件
+struct IccProfileValue<'a>(&'a [u8]);
+
+impl TiffEncodeValue for IccProfileValue<'_> {
+    const BYTE_LEN: u8 = 1;
+    const FIELD_TYPE: TiffType = TiffType::UNDEFINED;
 
-    params.set_color_type(decoder.colortype()?);
-    let (width, height) = decoder.dimensions()?;
-    params.set_source_dimensions(width, height);
+    fn count(&self) -> usize {
+        self.0.len()
+    }
 
-    if read_tags {
-        let resolution_unit = decoder.get_tag(TiffTag::ResolutionUnit)?.into_u32()?;
-        let x_resolution = decoder.get_tag(TiffTag::XResolution)?;
-        let y_resolution = decoder.get_tag(TiffTag::YResolution)?;
-        params.set_resolution(resolution_unit, x_resolution, y_resolution);
+    fn data(&self) -> std::borrow::Cow<'_, [u8]> {
+        std::borrow::Cow::Borrowed(self.0)
     }
+}
 
-    Ok(params)
+/// 检查 ICC 配置文件头：文件签名须为 `acsp`，颜色空间须与输出颜色类型一致
+pub(crate) fn check_icc_profile(
+    icc_profile: &[u8],
+    color_type: Option<tiff::ColorType>,
+) -> Result<()> {
+    if icc_profile.len() < 128 || &icc_profile[36..40] != b"acsp" {
+        return Err(Error::InvalidInput(
+            "ICC 配置文件无效：缺少 acsp 文件签名".to_string(),
+        ));
+    }
+    let expected: &[u8] = match color_type {
+        Some(tiff::ColorType::CMYK(_)) => b"CMYK",
+        Some(tiff::ColorType::RGB(_)) => b"RGB ",
+        Some(tiff::ColorType::Gray(_)) => b"GRAY",
+        _ => return Ok(()),
+    };
+    let color_space = &icc_profile[16..20];
+    if color_space != expected {
+        return Err(Error::InvalidInput(format!(
+            "ICC 配置文件的颜色空间 {} 与输出颜色类型 {:?} 不一致",
+            String::from_utf8_lossy(color_space).trim_end(),
+            color_type
+        )));
+    }
+    Ok(())
 }
 
 /// 判断两个图片的基础参数是否一致
 fn is_matching_params(base: &SourceParams, other: &SourceParams) -> bool {
-    other.color_type.is_some()
-        && base.color_type == other.color_type
-        && base.width == other.width
-        && base.height == other.height
+    is_matching_color_type(base, other) && base.width == other.width && base.height == other.height
+}
+
+/// 判断两个图片的颜色类型是否一致
+fn is_matching_color_type(base: &SourceParams, other: &SourceParams) -> bool {
+    other.color_type.is_some() && base.color_type == other.color_type
 }
 
 #[cfg(test)]
 mod tests {
 
-    use tiff::encoder::{colortype, compression::Lzw, Rational};
+    use tiff::{
+        decoder::DecodingResult as TiffDecodingResult,
+        encoder::{colortype, compression::Lzw, Rational},
+    };
+
+    use std::io::Cursor;
+
+    use crate::{
+        decoder::{MemoryFrameDecoder, TiffFrameDecoder},
+        lenticular::test_util::{icc_profile, input_frame, source_params},
+    };
 
     use super::*;
 
@@ -376,14 +1307,14 @@ mod tests {
             let file = std::fs::File::open(input).unwrap();
             let reader = std::io::BufReader::new(file);
             inputs.push(InputImageContext::new(
-                reader,
+                TiffFrameDecoder::new(reader),
                 ImageOptions {
                     lenticular_width_px: 1,
                 },
             ));
         }
 
-        let opt = ProcessOptions::new(91.60, 10.6);
+        let opt = ProcessOptions::new(91.60, Length::cm(10.6));
         let output_info = opt.calc_output_info(&mut inputs).unwrap();
         let out = opt
             .process_tiff_cmyk8(inputs, &output_info, ScaleAlgorithm::Nearest)
@@ -408,7 +1339,7 @@ mod tests {
                 concat!("lenticular-image-tool", " ", env!("CARGO_PKG_VERSION")),
             )
             .unwrap();
-            e.write_tag(TiffTag::ResolutionUnit, 2).unwrap();
+            e.write_tag(TiffTag::ResolutionUnit, 2u16).unwrap();
             e.write_tag(
                 TiffTag::XResolution,
                 Rational {
@@ -428,4 +1359,350 @@ mod tests {
         }
         out_tiff_img.write_data(&out.to_bytes()).unwrap();
     }
+
+    #[test]
+    fn test_process_tiff_cmyk16() {
+        // 两张 CMYK16 平涂图，光栅宽度各 1px
+        let inputs = [0x1234u16, 0xfedc]
+            .into_iter()
+            .map(|value| {
+                let mut buf = std::io::Cursor::new(Vec::new());
+                let mut encoder = tiff::encoder::TiffEncoder::new(&mut buf).unwrap();
+                let mut image = encoder.new_image::<colortype::CMYK16>(8, 4).unwrap();
+                let e = image.encoder();
+                e.write_tag(TiffTag::ResolutionUnit, 2u16).unwrap();
+                e.write_tag(TiffTag::XResolution, Rational { n: 300, d: 1 })
+                    .unwrap();
+                e.write_tag(TiffTag::YResolution, Rational { n: 300, d: 1 })
+                    .unwrap();
+                image.write_data(&[value; 8 * 4 * 4]).unwrap();
+                buf.set_position(0);
+                InputImageContext::new(
+                    TiffFrameDecoder::new(buf),
+                    ImageOptions {
+                        lenticular_width_px: 1,
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut inputs = inputs;
+
+        let opt = ProcessOptions::new(10.0, Length::cm(3.0)).with_lzw(true);
+        let output_info = opt.calc_output_info(&mut inputs).unwrap();
+        assert_eq!(output_info.width, 22);
+        let out = opt
+            .process_tiff_cmyk16(inputs, &output_info, ScaleAlgorithm::Nearest)
+            .unwrap();
+        assert!(out.lzw());
+        let row = out.inner().row(0);
+        assert!(row.iter().step_by(2).all(|c| c.k == 0x1234));
+        assert!(row.iter().skip(1).step_by(2).all(|c| c.k == 0xfedc));
+
+        let mut written = std::io::Cursor::new(Vec::new());
+        write_tiff_cmyk16(&mut written, &out).unwrap();
+        written.set_position(0);
+        let mut decoder = tiff::decoder::Decoder::new(written).unwrap();
+        assert_eq!(decoder.colortype().unwrap(), tiff::ColorType::CMYK(16));
+        // 5：LZW 压缩
+        assert_eq!(decoder.get_tag_u32(TiffTag::Compression).unwrap(), 5);
+        let TiffDecodingResult::U16(samples) = decoder.read_image().unwrap() else {
+            panic!("expected 16-bit samples");
+        };
+        assert_eq!(samples, out.to_samples());
+    }
+
+    /// 生成 4x4 的 CMYK8 平涂图，可选嵌入 ICC 配置文件
+    fn cmyk8_tiff(value: u8, icc_profile: Option<&[u8]>) -> TiffFrameDecoder<Cursor<Vec<u8>>> {
+        let mut buf = Cursor::new(Vec::new());
+        let mut encoder = tiff::encoder::TiffEncoder::new(&mut buf).unwrap();
+        let mut image = encoder.new_image::<colortype::CMYK8>(4, 4).unwrap();
+        let e = image.encoder();
+        e.write_tag(TiffTag::ResolutionUnit, 2u16).unwrap();
+        e.write_tag(TiffTag::XResolution, Rational { n: 300, d: 1 })
+            .unwrap();
+        e.write_tag(TiffTag::YResolution, Rational { n: 300, d: 1 })
+            .unwrap();
+        if let Some(icc_profile) = icc_profile {
+            e.write_tag(ICC_PROFILE_TAG, IccProfileValue(icc_profile))
+                .unwrap();
+        }
+        image.write_data(&[value; 4 * 4 * 4]).unwrap();
+        buf.set_position(0);
+        TiffFrameDecoder::new(buf)
+    }
+
+    #[test]
+    fn test_icc_profile_pass_through() {
+        let options = ImageOptions {
+            lenticular_width_px: 1,
+        };
+        let profile = icc_profile(b"CMYK");
+        let opt = ProcessOptions::new(10.0, Length::cm(3.0));
+
+        // 配置文件一致时写入输出
+        let mut inputs = vec![
+            InputImageContext::new(cmyk8_tiff(10, Some(&profile)), options.clone()),
+            InputImageContext::new(cmyk8_tiff(20, Some(&profile)), options.clone()),
+        ];
+        let output_info = opt.calc_output_info(&mut inputs).unwrap();
+        let out = opt
+            .process_tiff_cmyk8(inputs, &output_info, ScaleAlgorithm::Nearest)
+            .unwrap();
+        assert_eq!(out.icc_profile(), Some(profile.as_slice()));
+
+        let mut written = Cursor::new(Vec::new());
+        write_tiff_cmyk8(&mut written, &out).unwrap();
+        written.set_position(0);
+        let params = TiffFrameDecoder::new(written).read_params(true).unwrap();
+        assert_eq!(params.icc_profile, Some(profile.clone()));
+
+        // 配置文件不一致时报错
+        let mut inputs = vec![
+            InputImageContext::new(cmyk8_tiff(10, Some(&profile)), options.clone()),
+            InputImageContext::new(cmyk8_tiff(20, None), options.clone()),
+        ];
+        let output_info = opt.calc_output_info(&mut inputs).unwrap();
+        assert!(opt
+            .process_tiff_cmyk8(inputs, &output_info, ScaleAlgorithm::Nearest)
+            .is_err());
+
+        // 用户指定的配置文件优先
+        let mut custom = icc_profile(b"CMYK");
+        custom[128..].copy_from_slice(b"override");
+        let opt = opt.with_icc_profile(custom.clone());
+        let mut inputs = vec![InputImageContext::new(
+            cmyk8_tiff(10, Some(&profile)),
+            options.clone(),
+        )];
+        let output_info = opt.calc_output_info(&mut inputs).unwrap();
+        assert_eq!(output_info.icc_profile(), Some(custom.as_slice()));
+
+        // 颜色空间与输出不一致或缺少文件签名时报错
+        for invalid in [icc_profile(b"RGB "), b"fake cmyk profile".to_vec()] {
+            let opt = ProcessOptions::new(10.0, Length::cm(3.0)).with_icc_profile(invalid);
+            let mut inputs = vec![InputImageContext::new(
+                cmyk8_tiff(10, None),
+                options.clone(),
+            )];
+            assert!(opt.calc_output_info(&mut inputs).is_err());
+        }
+    }
+
+    #[test]
+    fn test_process_rgb_with_cmyk_separation() {
+        let mut params = source_params(tiff::ColorType::RGB(8), 4, 4);
+        params.set_icc_profile(Some(icc_profile(b"RGB ")));
+        let frame = MemoryFrameDecoder::new(params, FrameData::U8(vec![0; 4 * 4 * 3]));
+        let mut inputs = vec![InputImageContext::new(
+            frame,
+            ImageOptions {
+                lenticular_width_px: 1,
+            },
+        )];
+
+        let opt = ProcessOptions::new(10.0, Length::cm(3.0))
+            .with_cmyk_separation(CmykSeparation::default());
+        let output_info = opt.calc_output_info(&mut inputs).unwrap();
+        assert_eq!(output_info.color_type(), Some(tiff::ColorType::CMYK(8)));
+        assert_eq!(output_info.icc_profile(), None);
+
+        let out = opt
+            .process_tiff_cmyk8(inputs, &output_info, ScaleAlgorithm::Nearest)
+            .unwrap();
+        let px = out.inner()[[0, 0]];
+        let total = px.c as u32 + px.m as u32 + px.y as u32 + px.k as u32;
+        assert!(px.k > 0);
+        assert!(total <= 255 * 3, "total ink: {total}");
+    }
+
+    #[test]
+    fn test_fit_mode_with_mixed_sizes() {
+        let rgb8_frame = |width: u32, height: u32, rgb: [u8; 3]| {
+            let data = rgb.repeat((width * height) as usize);
+            input_frame(tiff::ColorType::RGB(8), width, height, data, 1)
+        };
+        let red = Rgb8Color { r: 255, g: 0, b: 0 };
+        let blue = Rgb8Color { r: 0, g: 0, b: 255 };
+        let white = Rgb8Color {
+            r: 255,
+            g: 255,
+            b: 255,
+        };
+        let inputs = || vec![rgb8_frame(4, 4, [255, 0, 0]), rgb8_frame(8, 4, [0, 0, 255])];
+
+        // 尺寸不一致时拉伸模式报错
+        let opt = ProcessOptions::new(10.0, Length::cm(3.0));
+        let mut stretch_inputs = inputs();
+        let output_info = opt.calc_output_info(&mut stretch_inputs).unwrap();
+        assert!(opt
+            .process_tiff_rgb8(stretch_inputs, &output_info, ScaleAlgorithm::Nearest)
+            .is_err());
+
+        // 填满：第二张图裁切为正方形，整列均为原色
+        let opt = ProcessOptions::new(10.0, Length::cm(3.0)).with_fit_mode(FitMode::Fill {
+            anchor: Default::default(),
+        });
+        let mut fill_inputs = inputs();
+        let output_info = opt.calc_output_info(&mut fill_inputs).unwrap();
+        assert_eq!((output_info.width, output_info.height), (22, 22));
+        let out = opt
+            .process_tiff_rgb8(fill_inputs, &output_info, ScaleAlgorithm::Nearest)
+            .unwrap();
+        assert!(out.inner().column(0).iter().all(|c| *c == red));
+        assert!(out.inner().column(1).iter().all(|c| *c == blue));
+
+        // 完整显示：第二张图上下留白
+        let opt = ProcessOptions::new(10.0, Length::cm(3.0))
+            .with_fit_mode(FitMode::Fit { background: white })
+            .with_physical_height(Length::cm(3.0));
+        let mut fit_inputs = inputs();
+        let output_info = opt.calc_output_info(&mut fit_inputs).unwrap();
+        let out = opt
+            .process_tiff_rgb8(fit_inputs, &output_info, ScaleAlgorithm::Nearest)
+            .unwrap();
+        let column = out.inner().column(1);
+        assert_eq!(column[0], white);
+        assert_eq!(column[11], blue);
+        assert_eq!(column[21], white);
+    }
+
+    #[test]
+    fn test_reverse_print_mirrors_interlace() {
+        // 两帧水平渐变，光栅宽度不等
+        let inputs = || {
+            [(1, 0), (2, 100)]
+                .into_iter()
+                .map(|(lenticular_width_px, base)| {
+                    let data = (0..10)
+                        .flat_map(|_| (0..30u8).flat_map(move |x| [base + x, 0, 0]))
+                        .collect();
+                    input_frame(tiff::ColorType::RGB(8), 30, 10, data, lenticular_width_px)
+                })
+                .collect::<Vec<_>>()
+        };
+        let process = |opt: ProcessOptions| {
+            let mut inputs = inputs();
+            let output_info = opt.calc_output_info(&mut inputs).unwrap();
+            opt.process_tiff_rgb8(inputs, &output_info, ScaleAlgorithm::Nearest)
+                .unwrap()
+        };
+
+        let normal = process(ProcessOptions::new(10.0, Length::inches(1.0)));
+        let reverse =
+            process(ProcessOptions::new(10.0, Length::inches(1.0)).with_reverse_print(true));
+        assert_eq!(reverse.layout().unwrap().lenticular_widths, [2, 1]);
+        // 输出宽度为整数个光栅，背面印刷结果与正面印刷的镜像一致
+        assert_eq!(reverse.inner(), normal.inner().slice(s![.., ..;-1]));
+        // 每个光栅的前两列来自第二帧
+        assert!(reverse.inner()[[0, 0]].r >= 100 && reverse.inner()[[0, 2]].r < 100);
+    }
+
+    #[test]
+    fn test_output_dimensions() {
+        let frame = || input_frame(tiff::ColorType::RGB(8), 40, 20, vec![0; 40 * 20 * 3], 1);
+
+        // 只指定高度时宽度按宽高比计算
+        let opt = ProcessOptions::from_height(10.0, Length::cm(3.0));
+        let output_info = opt.calc_output_info(&mut [frame()]).unwrap();
+        assert_eq!(output_info.width, 23);
+        assert_eq!(output_info.height, 12);
+        let tolerance = Length::from_px(0.5, output_info.dpi_h).to_mm();
+        assert!((output_info.physical_height.to_mm() - 30.0).abs() <= tolerance + 1e-9);
+
+        // 卡片尺寸：宽高与原图比例无关
+        let opt = ProcessOptions::from_dimensions(100.0, Length::mm(85.6), Length::mm(54.0))
+            .with_fit_mode(FitMode::Fill {
+                anchor: Default::default(),
+            });
+        let output_info = opt.calc_output_info(&mut [frame()]).unwrap();
+        let width = Length::from_px(output_info.width as f64, output_info.dpi_w);
+        let height = Length::from_px(output_info.height as f64, output_info.dpi_h);
+        assert!((width.to_mm() - 85.6).abs() < 1e-9);
+        assert!((height.to_mm() - 54.0).abs() <= Length::from_px(0.5, output_info.dpi_h).to_mm());
+        assert_eq!(output_info.physical_width, Length::mm(85.6));
+    }
+
+    #[test]
+    fn test_bleed_and_crop_marks() {
+        let options = ImageOptions {
+            lenticular_width_px: 1,
+        };
+        let mut inputs = vec![
+            InputImageContext::new(cmyk8_tiff(10, None), options.clone()),
+            InputImageContext::new(cmyk8_tiff(20, None), options),
+        ];
+        let opt = ProcessOptions::new(10.0, Length::cm(3.0))
+            .with_bleed(Length::mm(3.0))
+            .with_crop_marks(true);
+        let output_info = opt.calc_output_info(&mut inputs).unwrap();
+        let layout = output_info.layout.clone();
+        // 3mm 出血向上取整为 2 个光栅
+        assert_eq!(layout.trim_width, 22);
+        assert_eq!(layout.bleed_x, 4);
+        assert_eq!(output_info.width, 30);
+
+        let out = opt
+            .process_tiff_cmyk8(inputs, &output_info, ScaleAlgorithm::Nearest)
+            .unwrap();
+        // 出血区域同样为光栅合成的画面
+        assert!(out.inner().row(0).iter().step_by(2).all(|c| c.k == 10));
+        assert!(out
+            .inner()
+            .row(0)
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .all(|c| c.k == 20));
+
+        let mut written = Cursor::new(Vec::new());
+        write_tiff_cmyk8(&mut written, &out).unwrap();
+        written.set_position(0);
+        let params = TiffFrameDecoder::new(written).read_params(true).unwrap();
+        assert_eq!(params.width, 30 + layout.slug() * 2);
+        assert_eq!(params.height, output_info.height + layout.slug() * 2);
+
+        // 白墨作为第 5 个油墨通道，与标记区域一起写出
+        let white: MatrixImage<Gray8Color> = MatrixImage::new(out.width(), out.height());
+        let mut written = Cursor::new(Vec::new());
+        write_tiff_cmykw8(&mut written, &out, &white).unwrap();
+        let names = b"Cyan\0Magenta\0Yellow\0Black\0White\0";
+        assert!(written.get_ref().windows(names.len()).any(|w| w == names));
+        written.set_position(0);
+        let mut decoder = tiff::decoder::Decoder::new(written).unwrap();
+        assert_eq!(decoder.get_tag_u32(INK_SET_TAG).unwrap(), 2);
+        assert_eq!(decoder.get_tag_u32(NUMBER_OF_INKS_TAG).unwrap(), 5);
+        assert!(decoder.find_tag(TiffTag::ExtraSamples).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_bleed_keeps_trim_content() {
+        // 水平、垂直方向都有渐变的帧
+        let frame = |offset: u8| {
+            let data = (0..6u8)
+                .flat_map(|y| (0..8u8).flat_map(move |x| [x * 20 + offset, y * 30, 0, 0]))
+                .collect();
+            input_frame(tiff::ColorType::CMYK(8), 8, 6, data, 1)
+        };
+        let process = |opt: ProcessOptions| {
+            let mut inputs = vec![frame(0), frame(5)];
+            let output_info = opt.calc_output_info(&mut inputs).unwrap();
+            opt.process_tiff_cmyk8(inputs, &output_info, ScaleAlgorithm::Bilinear)
+                .unwrap()
+        };
+
+        let plain = process(ProcessOptions::new(10.0, Length::cm(3.0)));
+        let bleed = process(ProcessOptions::new(10.0, Length::cm(3.0)).with_bleed(Length::mm(3.0)));
+        let layout = bleed.layout().unwrap();
+        let (bx, by) = (layout.bleed_x as usize, layout.bleed_y as usize);
+        assert!(by > 0);
+
+        // 成品区域与不加出血时一致，出血区域复制边缘像素
+        let trim = bleed.inner().slice(s![
+            by..by + plain.height() as usize,
+            bx..bx + plain.width() as usize
+        ]);
+        assert_eq!(trim, plain.inner());
+        assert_eq!(bleed.inner().row(0), bleed.inner().row(by));
+        assert_eq!(bleed.inner()[[by, 0]], plain.inner()[[0, 0]]);
+    }
 }
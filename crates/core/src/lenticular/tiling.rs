@@ -0,0 +1,209 @@
+use log::debug;
+use ndarray::s;
+
+use crate::{
+    error::{Error, Result},
+    image::{Color, MatrixImage},
+    units::Length,
+};
+
+use super::OutputInfo;
+
+/// 大幅面分块选项
+#[derive(Debug, Clone)]
+pub struct TilingOptions {
+    /// 单块最大宽度，向下取整到整数个光栅
+    pub panel_width: Length,
+    /// 单块最大高度
+    pub panel_height: Length,
+    /// 相邻块的重叠宽度，水平方向向上取整到整数个光栅
+    pub overlap: Length,
+}
+
+/// 分块在光栅合成图像中的位置，单位：像素
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panel {
+    /// 行序号，从 0 开始
+    pub row: u32,
+    /// 列序号，从 0 开始
+    pub column: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Panel {
+    /// 按行列序号生成文件名，如 `wall_r01c02`（序号从 1 开始）
+    pub fn name(&self, stem: &str) -> String {
+        format!("{}_r{:02}c{:02}", stem, self.row + 1, self.column + 1)
+    }
+}
+
+/// 按输出图像信息计算分块，各块的左右边界均落在光栅边界上
+pub fn plan_panels(output_info: &OutputInfo, options: &TilingOptions) -> Result<Vec<Panel>> {
+    let lenticular_width: u32 = output_info.layout.lenticular_widths.iter().sum();
+    if lenticular_width == 0 {
+        return Err(Error::InvalidInput(
+            "缺少光栅宽度信息，无法分块".to_string(),
+        ));
+    }
+    if options.overlap.to_mm() < 0.0 {
+        return Err(Error::InvalidInput("重叠宽度不能为负".to_string()));
+    }
+
+    let lenses = |length: Length, round: fn(f64) -> f64| {
+        round(length.to_px(output_info.dpi_w).max(0.0) / lenticular_width as f64) as u32
+            * lenticular_width
+    };
+    let panel_w = lenses(options.panel_width, f64::floor);
+    let overlap_x = lenses(options.overlap, f64::ceil);
+    let panel_h = options.panel_height.to_px(output_info.dpi_h).floor() as u32;
+    let overlap_y = options.overlap.to_px(output_info.dpi_h).ceil() as u32;
+    if panel_w <= overlap_x || panel_h <= overlap_y {
+        return Err(Error::InvalidInput(format!(
+            "分块尺寸 {}x{} 像素必须大于重叠宽度 {}x{} 像素",
+            panel_w, panel_h, overlap_x, overlap_y
+        )));
+    }
+
+    let columns = split_axis(output_info.width, panel_w, overlap_x);
+    let rows = split_axis(output_info.height, panel_h, overlap_y);
+    let panels = rows
+        .iter()
+        .enumerate()
+        .flat_map(|(row, &(y, height))| {
+            columns
+                .iter()
+                .enumerate()
+                .map(move |(column, &(x, width))| Panel {
+                    row: row as u32,
+                    column: column as u32,
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+        })
+        .collect::<Vec<_>>();
+    debug!(
+        "panels: {} columns x {} rows, panel {}x{}, overlap {}x{}",
+        columns.len(),
+        rows.len(),
+        panel_w,
+        panel_h,
+        overlap_x,
+        overlap_y
+    );
+
+    Ok(panels)
+}
+
+/// 将长度 `total` 按块长与重叠切分，返回各块的起点与长度
+fn split_axis(total: u32, panel: u32, overlap: u32) -> Vec<(u32, u32)> {
+    let mut out = vec![];
+    let mut start = 0;
+    loop {
+        out.push((start, panel.min(total - start)));
+        if start + panel >= total {
+            break;
+        }
+        start += panel - overlap;
+    }
+    out
+}
+
+/// 从光栅合成图像中裁出一块，保留分辨率与 ICC 配置文件
+pub fn crop_panel<C>(img: &MatrixImage<C>, panel: &Panel) -> MatrixImage<C>
+where
+    C: Color,
+{
+    let (x, y) = (panel.x as usize, panel.y as usize);
+    let (w, h) = (panel.width as usize, panel.height as usize);
+
+    let mut out: MatrixImage<C> = MatrixImage::new(panel.width, panel.height);
+    out.inner_mut()
+        .assign(&img.inner().slice(s![y..y + h, x..x + w]));
+    if let Some(info) = img.info() {
+        out.set_info(info.clone());
+    }
+    out.set_icc_profile(img.icc_profile().map(|p| p.to_vec()));
+    out.set_lzw(img.lzw());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lenticular::{FitMode, PageLayout, SourceParams};
+
+    use super::*;
+
+    #[test]
+    fn test_plan_panels_on_lens_boundaries() {
+        // 254 DPI：10 像素 = 1mm，每个光栅 3 像素
+        let output_info = OutputInfo {
+            width: 300,
+            height: 100,
+            dpi_w: 254.0,
+            dpi_h: 254.0,
+            physical_width: Length::mm(30.0),
+            physical_height: Length::mm(10.0),
+            layout: PageLayout {
+                trim_width: 300,
+                trim_height: 100,
+                lenticular_widths: vec![2, 1],
+                ..Default::default()
+            },
+            icc_override: None,
+            cmyk_separation: None,
+            lzw: false,
+            fit_mode: FitMode::Stretch,
+            frame_sizes: vec![],
+            source_params: SourceParams::default(),
+        };
+        let options = TilingOptions {
+            panel_width: Length::mm(10.05),
+            panel_height: Length::mm(6.0),
+            overlap: Length::mm(1.05),
+        };
+
+        // 块宽 100.5 像素向下取整为 33 个光栅，重叠 10.5 像素向上取整为 4 个光栅
+        let panels = plan_panels(&output_info, &options).unwrap();
+        assert!(panels
+            .iter()
+            .all(|panel| panel.x % 3 == 0 && panel.width % 3 == 0));
+        assert!(panels
+            .iter()
+            .all(|panel| panel.x + panel.width <= output_info.width));
+        let first_row = panels
+            .iter()
+            .filter(|panel| panel.row == 0)
+            .collect::<Vec<_>>();
+        assert_eq!(first_row[0].width, 99);
+        assert_eq!(first_row[1].x, 99 - 12);
+        assert_eq!(
+            first_row.last().unwrap().x + first_row.last().unwrap().width,
+            300
+        );
+        assert_eq!(panels.iter().map(|panel| panel.row).max(), Some(1));
+
+        // 块宽不大于重叠宽度或重叠宽度为负时报错
+        let too_narrow = TilingOptions {
+            panel_width: Length::mm(1.2),
+            ..options.clone()
+        };
+        assert!(plan_panels(&output_info, &too_narrow).is_err());
+        let negative = TilingOptions {
+            overlap: Length::mm(-1.0),
+            ..options
+        };
+        assert!(plan_panels(&output_info, &negative).is_err());
+    }
+
+    #[test]
+    fn test_split_axis() {
+        assert_eq!(split_axis(30, 10, 2), [(0, 10), (8, 10), (16, 10), (24, 6)]);
+        assert_eq!(split_axis(10, 10, 2), [(0, 10)]);
+        assert_eq!(split_axis(12, 10, 0), [(0, 10), (10, 2)]);
+    }
+}
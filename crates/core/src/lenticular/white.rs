@@ -0,0 +1,248 @@
+use log::debug;
+
+use crate::{
+    decoder::{FrameData, FrameDecoder, MemoryFrameDecoder},
+    error::{Error, Result},
+    image::{Cmyk8Color, Gray8Color, MatrixImage},
+};
+
+use super::{process_tiff, rgb8_luma, ImageOptions, InputImageContext, OutputInfo, ScaleAlgorithm};
+
+/// 按总墨量生成白墨通道：总墨量超过 `threshold`（百分比）的像素铺满白墨
+///
+/// `threshold` 小于 0 时整版铺白。
+pub fn white_ink_from_coverage(
+    img: &MatrixImage<Cmyk8Color>,
+    threshold: f64,
+) -> MatrixImage<Gray8Color> {
+    let mut out: MatrixImage<Gray8Color> = MatrixImage::new(img.width(), img.height());
+    out.inner_mut().zip_mut_with(img.inner(), |white, c| {
+        let coverage = (c.c as u32 + c.m as u32 + c.y as u32 + c.k as u32) as f64 / 255.0 * 100.0;
+        white.v = if coverage > threshold { 255 } else { 0 };
+    });
+    copy_meta(img, &mut out);
+    out
+}
+
+/// 将逐帧的白墨蒙版按与彩色数据完全相同的列映射合成为白墨通道
+///
+/// 蒙版数量必须与帧数一致，白色（亮度高）表示铺白墨。蒙版可以是灰度或 RGB 图像，
+/// 尺寸必须与对应的帧一致，按与帧相同的适配方式裁切、缩放，保证与彩色数据对位。
+pub fn process_white_ink_masks<D>(
+    masks: Vec<D>,
+    output_info: &OutputInfo,
+    scale_alg: ScaleAlgorithm,
+) -> Result<MatrixImage<Gray8Color>>
+where
+    D: FrameDecoder,
+{
+    let layout = &output_info.layout;
+    if masks.len() != layout.lenticular_widths.len() {
+        return Err(Error::InvalidInput(format!(
+            "白墨蒙版数量 {} 与帧数 {} 不一致",
+            masks.len(),
+            layout.lenticular_widths.len()
+        )));
+    }
+
+    // 版面中的光栅宽度表按光栅内位置排列，背面印刷时需还原为输入顺序
+    let mut widths = layout.lenticular_widths.clone();
+    if layout.reverse_print {
+        widths.reverse();
+    }
+
+    let mut source_params = None;
+    let inputs = masks
+        .into_iter()
+        .zip(widths)
+        .enumerate()
+        .map(|(index, (mut decoder, lenticular_width_px))| {
+            let mut params = decoder.read_params(false)?;
+            let mismatched = output_info
+                .frame_sizes
+                .get(index)
+                .filter(|&&size| size != (params.width, params.height));
+            if let Some((width, height)) = mismatched {
+                return Err(Error::InvalidInput(format!(
+                    "白墨蒙版 {:02} 的尺寸 {}x{} 与对应帧的尺寸 {}x{} 不一致",
+                    index, params.width, params.height, width, height
+                )));
+            }
+            let data = decoder.read_image()?;
+            let gray = mask_to_gray8(params.color_type, data).ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "白墨蒙版 {:02} 的颜色类型 {:?} 不受支持，仅接受灰度或 RGB 图像",
+                    index, params.color_type
+                ))
+            })?;
+            params.set_color_type(tiff::ColorType::Gray(8));
+            params.set_icc_profile(None);
+            source_params.get_or_insert_with(|| params.clone());
+            Ok(InputImageContext::new(
+                MemoryFrameDecoder::new(params, FrameData::U8(gray)),
+                ImageOptions {
+                    lenticular_width_px,
+                },
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut mask_info = output_info.clone();
+    mask_info.source_params = source_params.unwrap_or_default();
+    mask_info.cmyk_separation = None;
+    mask_info.icc_override = None;
+    debug!("white ink mask source: {:?}", mask_info.source_params);
+
+    process_tiff(inputs, &mask_info, scale_alg)
+}
+
+/// 将蒙版帧转换为 8位灰度，16位采样截取高 8 位
+fn mask_to_gray8(color_type: Option<tiff::ColorType>, data: FrameData) -> Option<Vec<u8>> {
+    let data = match data {
+        FrameData::U8(data) => data,
+        FrameData::U16(data) => data.iter().map(|v| (v >> 8) as u8).collect(),
+    };
+    match color_type? {
+        tiff::ColorType::Gray(8 | 16) => Some(data),
+        tiff::ColorType::RGB(8 | 16) => Some(
+            data.chunks_exact(3)
+                .map(|p| rgb8_luma(p[0], p[1], p[2]))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn copy_meta(src: &MatrixImage<Cmyk8Color>, dst: &mut MatrixImage<Gray8Color>) {
+    if let Some(info) = src.info() {
+        dst.set_info(info.clone());
+    }
+    dst.set_layout(src.layout().cloned());
+    dst.set_lzw(src.lzw());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        lenticular::{
+            test_util::{input_frame, memory_frame},
+            Anchor, FitMode, ProcessOptions,
+        },
+        units::Length,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_white_ink_masks_follow_interlace() {
+        let frame = |width: u32, value: u8| {
+            memory_frame(
+                tiff::ColorType::Gray(8),
+                width,
+                4,
+                vec![value; (width * 4) as usize],
+            )
+        };
+        let inputs = || {
+            [(1, 10), (2, 20)]
+                .into_iter()
+                .map(|(lenticular_width_px, value)| {
+                    input_frame(
+                        tiff::ColorType::CMYK(8),
+                        4,
+                        4,
+                        vec![value; 4 * 4 * 4],
+                        lenticular_width_px,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for reverse_print in [false, true] {
+            let opt =
+                ProcessOptions::new(4.0, Length::inches(1.0)).with_reverse_print(reverse_print);
+            let output_info = opt.calc_output_info(&mut inputs()).unwrap();
+            // 第一帧蒙版全白，第二帧全黑
+            let white = process_white_ink_masks(
+                vec![frame(4, 255), frame(4, 0)],
+                &output_info,
+                ScaleAlgorithm::Nearest,
+            )
+            .unwrap();
+            let out = opt
+                .process_tiff_cmyk8(inputs(), &output_info, ScaleAlgorithm::Nearest)
+                .unwrap();
+            assert_eq!((white.width(), white.height()), (out.width(), out.height()));
+            for (c, w) in out.inner().iter().zip(white.inner().iter()) {
+                assert_eq!(w.v == 255, c.c == 10);
+            }
+        }
+
+        // 蒙版数量或尺寸与帧不一致时报错
+        assert!(process_white_ink_masks(
+            vec![frame(4, 255), frame(8, 0)],
+            &ProcessOptions::new(4.0, Length::inches(1.0))
+                .calc_output_info(&mut inputs())
+                .unwrap(),
+            ScaleAlgorithm::Nearest,
+        )
+        .is_err());
+        assert!(process_white_ink_masks(
+            vec![frame(4, 255)],
+            &ProcessOptions::new(4.0, Length::inches(1.0))
+                .calc_output_info(&mut inputs())
+                .unwrap(),
+            ScaleAlgorithm::Nearest,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_white_ink_mask_registration() {
+        // 帧与蒙版均为 8x4，宽高比与输出不同；左半边着色并铺白
+        let frame = || {
+            let data = (0..4)
+                .flat_map(|_| (0..8u8).flat_map(|x| [if x < 4 { 200 } else { 0 }, 0, 0, 0]))
+                .collect();
+            input_frame(tiff::ColorType::CMYK(8), 8, 4, data, 1)
+        };
+        let mask = || {
+            let data = (0..4)
+                .flat_map(|_| (0..8u8).map(|x| if x < 4 { 255 } else { 0 }))
+                .collect();
+            memory_frame(tiff::ColorType::Gray(8), 8, 4, data)
+        };
+
+        for fit_mode in [
+            FitMode::Fill {
+                anchor: Anchor::Left,
+            },
+            FitMode::Fit {
+                background: Default::default(),
+            },
+        ] {
+            let opt =
+                ProcessOptions::from_dimensions(4.0, Length::inches(3.0), Length::inches(2.0))
+                    .with_fit_mode(fit_mode);
+            let output_info = opt.calc_output_info(&mut [frame(), frame()]).unwrap();
+            let white = process_white_ink_masks(
+                vec![mask(), mask()],
+                &output_info,
+                ScaleAlgorithm::Nearest,
+            )
+            .unwrap();
+            let out = opt
+                .process_tiff_cmyk8(
+                    vec![frame(), frame()],
+                    &output_info,
+                    ScaleAlgorithm::Nearest,
+                )
+                .unwrap();
+            assert!(white.inner().iter().any(|w| w.v == 255));
+            assert!(white.inner().iter().any(|w| w.v == 0));
+            for (c, w) in out.inner().iter().zip(white.inner().iter()) {
+                assert_eq!(w.v == 255, c.c == 200);
+            }
+        }
+    }
+}
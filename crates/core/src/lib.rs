@@ -1,3 +1,6 @@
+pub mod analysis;
+pub mod decoder;
 pub mod error;
 pub mod image;
 pub mod lenticular;
+pub mod units;
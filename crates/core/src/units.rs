@@ -0,0 +1,172 @@
+use std::{fmt, str::FromStr};
+
+use crate::error::{Error, Result};
+
+/// 每英寸毫米数（精确值）
+const MM_PER_INCH: f64 = 25.4;
+/// 每英寸点数（PostScript 点）
+const PT_PER_INCH: f64 = 72.0;
+
+/// 长度单位
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    #[default]
+    Millimeter,
+    Centimeter,
+    Inch,
+    Point,
+}
+
+impl LengthUnit {
+    /// 单位的毫米数
+    fn mm(&self) -> f64 {
+        match self {
+            LengthUnit::Millimeter => 1.0,
+            LengthUnit::Centimeter => 10.0,
+            LengthUnit::Inch => MM_PER_INCH,
+            LengthUnit::Point => MM_PER_INCH / PT_PER_INCH,
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            LengthUnit::Millimeter => "mm",
+            LengthUnit::Centimeter => "cm",
+            LengthUnit::Inch => "in",
+            LengthUnit::Point => "pt",
+        }
+    }
+}
+
+/// 物理长度，内部以毫米保存
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Length {
+    mm: f64,
+}
+
+impl Length {
+    pub fn new(value: f64, unit: LengthUnit) -> Self {
+        Self {
+            mm: value * unit.mm(),
+        }
+    }
+
+    pub const fn mm(value: f64) -> Self {
+        Self { mm: value }
+    }
+
+    pub fn cm(value: f64) -> Self {
+        Self::new(value, LengthUnit::Centimeter)
+    }
+
+    pub fn inches(value: f64) -> Self {
+        Self::new(value, LengthUnit::Inch)
+    }
+
+    pub fn pt(value: f64) -> Self {
+        Self::new(value, LengthUnit::Point)
+    }
+
+    /// 按指定单位取值
+    pub fn to(&self, unit: LengthUnit) -> f64 {
+        self.mm / unit.mm()
+    }
+
+    pub fn to_mm(&self) -> f64 {
+        self.mm
+    }
+
+    pub fn to_cm(&self) -> f64 {
+        self.to(LengthUnit::Centimeter)
+    }
+
+    pub fn to_inches(&self) -> f64 {
+        self.to(LengthUnit::Inch)
+    }
+
+    pub fn to_pt(&self) -> f64 {
+        self.to(LengthUnit::Point)
+    }
+
+    /// 按给定 DPI 换算为像素数（未取整）
+    pub fn to_px(&self, dpi: f64) -> f64 {
+        self.to_inches() * dpi
+    }
+
+    /// 由像素数与 DPI 换算长度
+    pub fn from_px(px: f64, dpi: f64) -> Self {
+        Self::inches(px / dpi)
+    }
+}
+
+impl std::ops::Add for Length {
+    type Output = Length;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Length::mm(self.mm + rhs.mm)
+    }
+}
+
+impl std::ops::Mul<f64> for Length {
+    type Output = Length;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Length::mm(self.mm * rhs)
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}mm", self.mm)
+    }
+}
+
+/// 解析带单位的长度，如 `85.6mm`、`3.5in`、`10cm`、`72pt`
+///
+/// 未带单位时按毫米处理。
+impl FromStr for Length {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let units = [
+            LengthUnit::Millimeter,
+            LengthUnit::Centimeter,
+            LengthUnit::Inch,
+            LengthUnit::Point,
+        ];
+        let (value, unit) = units
+            .iter()
+            .find_map(|unit| {
+                s.strip_suffix(unit.suffix())
+                    .map(|value| (value.trim_end(), *unit))
+            })
+            .unwrap_or((s, LengthUnit::Millimeter));
+
+        let value: f64 = value
+            .parse()
+            .map_err(|_| Error::InvalidInput(format!("无效的长度：{}", s)))?;
+        if !value.is_finite() {
+            return Err(Error::InvalidInput(format!("无效的长度：{}", s)));
+        }
+        Ok(Length::new(value, unit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_length() {
+        assert_eq!(Length::inches(1.0).to_mm(), 25.4);
+        assert_eq!(Length::pt(72.0).to_inches(), 1.0);
+        assert!((Length::cm(2.54).to_inches() - 1.0).abs() < 1e-12);
+
+        assert_eq!("85.6mm".parse::<Length>().unwrap(), Length::mm(85.6));
+        assert_eq!("3 in".parse::<Length>().unwrap(), Length::inches(3.0));
+        assert_eq!("10cm".parse::<Length>().unwrap(), Length::mm(100.0));
+        assert_eq!("54".parse::<Length>().unwrap(), Length::mm(54.0));
+        assert!("abc".parse::<Length>().is_err());
+    }
+}